@@ -1,6 +1,8 @@
 //! Objects related to the "annotations" endpoint
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 
 use chrono::{DateTime, Utc};
 #[cfg(feature = "cli")]
@@ -9,6 +11,8 @@ use clap::Parser;
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use crate::groups::{is_valid_group_id, PUBLIC_GROUP_ID};
+use crate::text::normalize_quote;
 use crate::{errors, is_default, UserAccountID};
 
 #[cfg_attr(feature = "cli", derive(Parser))]
@@ -46,8 +50,9 @@ use crate::{errors, is_default, UserAccountID};
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Serialize, Debug, Default, Clone, Builder, PartialEq)]
-#[builder(default, build_fn(name = "builder"))]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Builder, PartialEq)]
+#[serde(default)]
+#[builder(default, build_fn(name = "builder", validate = "Self::validate"))]
 pub struct InputAnnotation {
     /// URI that this annotation is attached to.
     ///
@@ -77,22 +82,58 @@ pub struct InputAnnotation {
     #[serde(skip_serializing_if = "is_default")]
     /// The unique identifier for the annotation's group.
     ///
+    /// If left empty the annotation is posted to the Public group ([`PUBLIC_GROUP_ID`](crate::groups::PUBLIC_GROUP_ID)).
     /// If an annotation is a reply to another
     /// annotation (see `references`), this field will be ignored —
     /// replies belong to the same group as their parent annotations.
     #[cfg_attr(feature = "cli", clap(default_value = "", long))]
     #[builder(setter(into))]
     pub group: String,
-    /// Which part of the document does the annotation target?
+    /// Which parts of the document does the annotation target?
     ///
-    /// If left as default then the annotation is linked to the whole page.
-    #[serde(skip_serializing_if = "is_default")]
+    /// Multiple targets can describe the same segment with different selectors for more robust anchoring.
+    /// Use the `target` builder setter to add one target at a time.
+    /// If left empty then the annotation is linked to the whole page.
+    #[serde(
+        rename = "target",
+        skip_serializing_if = "is_default",
+        deserialize_with = "one_or_many"
+    )]
     #[cfg_attr(feature = "cli", clap(skip))]
-    pub target: Target,
+    #[builder(setter(each(name = "target")))]
+    pub targets: Vec<Target>,
     /// Annotation IDs for any annotations this annotation references (e.g. is a reply to)
     #[serde(skip_serializing_if = "is_default")]
     #[cfg_attr(feature = "cli", clap(long))]
     pub references: Vec<String>,
+    /// Information about the annotation's creator, e.g. the display name shown for
+    /// users of a third-party authority
+    #[serde(skip_serializing_if = "is_default")]
+    #[cfg_attr(feature = "cli", clap(skip))]
+    #[builder(setter(strip_option), default)]
+    pub user_info: Option<UserInfo>,
+    /// Who can read, update and delete the annotation, the API's default for the group if left empty
+    #[serde(skip_serializing_if = "is_default")]
+    #[cfg_attr(feature = "cli", clap(skip))]
+    #[builder(setter(strip_option), default)]
+    pub permissions: Option<Permissions>,
+}
+
+/// Accepts a single target object as well as the array form, for JSON written before `InputAnnotation` had multiple targets
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<Target>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Target),
+        Many(Vec<Target>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(target) => vec![target],
+        OneOrMany::Many(targets) => targets,
+    })
 }
 
 impl InputAnnotation {
@@ -102,6 +143,29 @@ impl InputAnnotation {
 }
 
 impl InputAnnotationBuilder {
+    /// Posts the annotation to the Public group, same as leaving `group` empty
+    pub fn public_group(&mut self) -> &mut Self {
+        self.group(PUBLIC_GROUP_ID)
+    }
+
+    /// Checks that `group` looks like a group ID
+    fn validate(&self) -> Result<(), String> {
+        match self.group.as_deref() {
+            Some(group) if !group.is_empty() && !is_valid_group_id(group) => Err(format!(
+                "`group` must be a group ID like \"{}\", got {:?}",
+                PUBLIC_GROUP_ID, group
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the display name of the annotation's creator in `user_info`
+    pub fn display_name<S: Into<String>>(&mut self, display_name: S) -> &mut Self {
+        self.user_info(UserInfo {
+            display_name: Some(display_name.into()),
+        })
+    }
+
     /// Builds a new `InputAnnotation`.
     pub fn build(&self) -> Result<InputAnnotation, errors::HypothesisError> {
         self.builder()
@@ -126,6 +190,167 @@ impl Annotation {
         if annotation.references.is_empty() {
             self.references = annotation.references;
         }
+        if let Some(permissions) = annotation.permissions {
+            self.permissions = permissions;
+        }
+    }
+
+    /// The text highlighted by the annotation,
+    /// i.e. the `exact` text of the first `TextQuoteSelector` across all targets
+    pub fn quote(&self) -> Option<&str> {
+        self.quotes().first().copied()
+    }
+
+    /// The `exact` text of every `TextQuoteSelector` across all targets
+    pub fn quotes(&self) -> Vec<&str> {
+        self.target
+            .iter()
+            .flat_map(|target| target.selector.iter())
+            .filter_map(|selector| match selector {
+                Selector::TextQuoteSelector(quote) => Some(quote.exact.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether one of the [`quotes`](Annotation::quotes) contains `quote`, comparing the texts
+    /// after [`normalize_quote`] so differences in whitespace don't matter
+    pub fn contains_quote(&self, quote: &str) -> bool {
+        let quote = normalize_quote(quote);
+        self.quotes()
+            .into_iter()
+            .any(|exact| normalize_quote(exact).contains(&quote))
+    }
+
+    /// Whether the annotation is a reply to another annotation
+    pub fn is_reply(&self) -> bool {
+        !self.references.is_empty()
+    }
+
+    /// ID of the top-level annotation of the thread this reply belongs to, `None` if it isn't a reply.
+    ///
+    /// `references` lists the reply's ancestors starting from the root, so this is the first of them.
+    pub fn root_reference(&self) -> Option<&str> {
+        self.references.first().map(String::as_str)
+    }
+
+    /// Shareable `hyp.is` URL that opens the annotated document with the annotation in context
+    ///
+    /// Built the same way as `links.incontext`, which isn't always included in API responses.
+    /// Replies link to their thread's root annotation. Only `http(s)` URIs are included in the URL.
+    pub fn share_url(&self) -> String {
+        let root = self.root_reference().unwrap_or(&self.id);
+        let mut url = format!("https://hyp.is/{}", root);
+        if let Some(rest) = self
+            .uri
+            .strip_prefix("http://")
+            .or_else(|| self.uri.strip_prefix("https://"))
+        {
+            url.push('/');
+            url.push_str(rest);
+        }
+        url
+    }
+
+    /// Orders annotations by creation date, then by ID for annotations created at the same time.
+    ///
+    /// `Annotation` doesn't implement `Ord` since equality compares every field, use this with `sort_by` instead:
+    /// ```
+    /// # use hypothesis::annotations::Annotation;
+    /// # fn sort(annotations: &mut Vec<Annotation>) {
+    /// annotations.sort_by(Annotation::cmp_by_created);
+    /// # }
+    /// ```
+    pub fn cmp_by_created(&self, other: &Self) -> Ordering {
+        self.created
+            .cmp(&other.created)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+
+    /// Converts the annotation into an `InputAnnotation`, e.g. to re-create it in another group.
+    ///
+    /// Keeps the annotation's own group if `group` is None.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::InputAnnotation;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let annotation = api.create_annotation(&InputAnnotation::builder()
+    ///                     .text("string")
+    ///                     .uri("http://example.com")
+    ///                     .tags(vec!["tag1".to_string(), "tag2".to_string()])
+    ///                     .build()?).await?;
+    /// let copy = api.create_annotation(&annotation.to_input(Some(&group_id))).await?;
+    /// assert_eq!(copy.text, annotation.text);
+    /// assert_eq!(copy.tags, annotation.tags);
+    /// assert_eq!(copy.group, group_id);
+    /// #    api.delete_annotations(&[annotation.id, copy.id]).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub fn to_input(&self, group: Option<&str>) -> InputAnnotation {
+        let mut input = InputAnnotation::from(self.clone());
+        if let Some(group) = group {
+            input.group = group.into();
+        }
+        input
+    }
+}
+
+/// Maximum number of characters of the quote shown by `Annotation`'s `Display`
+const MAX_DISPLAYED_QUOTE: usize = 40;
+/// Maximum number of characters of the text shown by `Annotation`'s `Display`
+const MAX_DISPLAYED_TEXT: usize = 80;
+
+/// Shortens `text` to at most `max` characters, ending with "…" if it was cut
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(max - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+/// A one-line summary, `[id] username: "quote" → text (tags)`, with the quote and text truncated.
+///
+/// Use `{:?}` to show all fields.
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}:", self.id, self.user.to_username())?;
+        if let Some(quote) = self.quote() {
+            write!(f, " \"{}\" →", truncate(quote, MAX_DISPLAYED_QUOTE))?;
+        }
+        write!(f, " {}", truncate(&self.text, MAX_DISPLAYED_TEXT))?;
+        if !self.tags.is_empty() {
+            write!(f, " ({})", self.tags.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Annotation> for InputAnnotation {
+    fn from(annotation: Annotation) -> Self {
+        Self {
+            uri: annotation.uri,
+            text: annotation.text,
+            tags: Some(annotation.tags),
+            document: annotation.document,
+            group: annotation.group,
+            targets: annotation.target,
+            references: annotation.references,
+            user_info: annotation.user_info,
+            // permissions refer to the annotation's group, which copies may not be created in
+            permissions: None,
+        }
     }
 }
 
@@ -133,6 +358,7 @@ impl Annotation {
 #[builder(build_fn(name = "builder"))]
 pub struct Document {
     #[serde(skip_serializing_if = "is_default", default)]
+    #[builder(setter(custom), default)]
     pub title: Vec<String>,
     #[serde(skip_serializing_if = "is_default", default)]
     #[builder(setter(strip_option), default)]
@@ -141,6 +367,7 @@ pub struct Document {
     #[builder(setter(strip_option), default)]
     pub highwire: Option<HighWire>,
     #[serde(skip_serializing_if = "is_default", default)]
+    #[builder(default)]
     pub link: Vec<Link>,
 }
 
@@ -151,6 +378,39 @@ impl Document {
 }
 
 impl DocumentBuilder {
+    /// Add a title of the document
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title
+            .get_or_insert_with(Vec::new)
+            .push(title.to_string());
+        self
+    }
+
+    /// Add a DOI (e.g. "10.1000/xyz123") to both `highwire.doi` and `dc.identifier`
+    pub fn doi(&mut self, doi: &str) -> &mut Self {
+        self.highwire
+            .get_or_insert(None)
+            .get_or_insert_with(HighWire::default)
+            .doi
+            .push(doi.to_string());
+        self.dc
+            .get_or_insert(None)
+            .get_or_insert_with(Dc::default)
+            .identifier
+            .push(doi.to_string());
+        self
+    }
+
+    /// Add the URL of the document's PDF to `highwire.pdf_url`
+    pub fn pdf_url(&mut self, pdf_url: &str) -> &mut Self {
+        self.highwire
+            .get_or_insert(None)
+            .get_or_insert_with(HighWire::default)
+            .pdf_url
+            .push(pdf_url.to_string());
+        self
+    }
+
     /// Builds a new `Document`.
     pub fn build(&self) -> Result<Document, errors::HypothesisError> {
         self.builder()
@@ -202,7 +462,7 @@ pub struct Annotation {
     /// Which part of the document does the annotation target.
     pub target: Vec<Target>,
     /// An object containing hypermedia links for this annotation
-    pub links: HashMap<String, String>,
+    pub links: AnnotationLinks,
     /// Whether this annotation is hidden from public view
     pub hidden: bool,
     /// Whether this annotation has one or more flags for moderation
@@ -215,14 +475,62 @@ pub struct Annotation {
     pub references: Vec<String>,
     #[serde(default)]
     pub user_info: Option<UserInfo>,
+    /// Moderation details, only returned by the API to moderators of the annotation's group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moderation: Option<Moderation>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Hypermedia links for an annotation
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct AnnotationLinks {
+    /// URL to the annotation's page on the Hypothesis website
+    pub html: Option<String>,
+    /// Shareable URL that opens the annotated document with the annotation in context
+    pub incontext: Option<String>,
+    /// URL to the annotation in the API
+    pub json: Option<String>,
+    /// Any other links
+    #[serde(flatten)]
+    pub extra: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct UserInfo {
     /// The annotation creator's display name
     pub display_name: Option<String>,
 }
 
+/// Moderation actions on an annotation, see [`Hypothesis::moderate_annotation`](crate::Hypothesis::moderate_annotation)
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Hide the annotation from other users
+    Hide,
+    /// Show a hidden annotation again
+    Show,
+    /// Flag the annotation for review by the group's moderators
+    Flag,
+}
+
+/// Moderation details of an annotation, see [`Annotation::moderation`]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Moderation {
+    /// How many users flagged the annotation
+    #[serde(rename = "flagCount")]
+    pub flag_count: u32,
+}
+
+/// Moderation state of an annotation
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ModerationStatus {
+    /// Whether a moderator has hidden the annotation
+    pub hidden: bool,
+    /// How many users flagged the annotation,
+    /// only returned by the API to moderators of the annotation's group
+    pub flag_count: Option<u32>,
+}
+
 /// > While the API accepts arbitrary Annotation selectors in the target.selector property,
 /// > the Hypothesis client currently supports TextQuoteSelector, RangeSelector and TextPositionSelector selector.
 /// [Hypothesis API v1.0.0](https://h.readthedocs.io/en/latest/api-reference/v1/#tag/annotations/paths/~1annotations/post)
@@ -260,39 +568,46 @@ impl TargetBuilder {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type")]
 pub enum Selector {
+    // NOTE: keep up to date with the Hypothesis API https://github.com/hypothesis/client/blob/main/src/types/api.ts
     TextQuoteSelector(TextQuoteSelector),
-    /// > Selections made by users may be extensive and/or cross over internal boundaries in the
-    /// > representation, making it difficult to construct a single selector that robustly describes
-    /// > the correct content. A Range Selector can be used to identify the beginning and the end of
-    /// > the selection by using other Selectors. In this way, two points can be accurately identified
-    /// > using the most appropriate selection mechanisms, and then linked together to form the selection.
-    /// > The selection consists of everything from the beginning of the starting selector through to the
-    /// > beginning of the ending selector, but not including it.
-    /// [Web Annotation Data Model - Range Selector](https://www.w3.org/TR/annotation-model/#range-selector)
-    /// NOTE - the Hypothesis API doesn't seem to follow this standard for RangeSelector so this just returns a HashMap for now
-    /// TODO: make Selectors into structs
-    /// NOTE: keep up to date with the Hypothesis API https://github.com/hypothesis/client/blob/main/src/types/api.ts
-    TextPositionSelector(HashMap<String, serde_json::Value>),
-    RangeSelector(HashMap<String, serde_json::Value>),
-    FragmentSelector(HashMap<String, serde_json::Value>),
-    CssSelector(HashMap<String, serde_json::Value>),
-    XPathSelector(HashMap<String, serde_json::Value>),
-    DataPositionSelector(HashMap<String, serde_json::Value>),
-    SvgSelector(HashMap<String, serde_json::Value>),
+    TextPositionSelector(TextPositionSelector),
+    RangeSelector(RangeSelector),
+    FragmentSelector(FragmentSelector),
+    CssSelector(CssSelector),
+    XPathSelector(XPathSelector),
+    DataPositionSelector(DataPositionSelector),
+    SvgSelector(SvgSelector),
     // See https://github.com/hypothesis/h/issues/7803:
-    PageSelector(HashMap<String, serde_json::Value>),
-    EPUBContentSelector(HashMap<String, serde_json::Value>),
-    MediaTimeSelector(HashMap<String, serde_json::Value>),
+    PageSelector(PageSelector),
+    EPUBContentSelector(EPUBContentSelector),
+    MediaTimeSelector(MediaTimeSelector),
 }
 
 impl Selector {
+    /// A `TextQuoteSelector`, with `exact` normalized by [`normalize_quote`]
+    ///
+    /// Whitespace trimmed from the start and end of `exact` is moved to the end of `prefix` and
+    /// the start of `suffix`, so the three still follow each other in the document.
     pub fn new_quote(exact: &str, prefix: &str, suffix: &str) -> Self {
+        let start = exact.len() - exact.trim_start().len();
+        let end = exact.trim_end().len().max(start);
         Self::TextQuoteSelector(TextQuoteSelector {
-            exact: exact.to_string(),
-            prefix: prefix.to_string(),
-            suffix: suffix.to_string(),
+            exact: normalize_quote(exact),
+            prefix: format!("{}{}", prefix, &exact[..start]),
+            suffix: format!("{}{}", &exact[end..], suffix),
+        })
+    }
+
+    pub fn new_text_position(start: usize, end: usize) -> Self {
+        Self::TextPositionSelector(TextPositionSelector {
+            start: Some(start),
+            end: Some(end),
         })
     }
+
+    pub fn new_media_time(start: f64, end: f64) -> Self {
+        Self::MediaTimeSelector(MediaTimeSelector { start, end })
+    }
 }
 
 /// > This Selector describes a range of text by copying it, and including some of the text
@@ -312,6 +627,148 @@ pub struct TextQuoteSelector {
     pub suffix: String,
 }
 
+/// > Selections made by users may be extensive and/or cross over internal boundaries in the
+/// > representation, making it difficult to construct a single selector that robustly describes
+/// > the correct content. A Range Selector can be used to identify the beginning and the end of
+/// > the selection by using other Selectors. In this way, two points can be accurately identified
+/// > using the most appropriate selection mechanisms, and then linked together to form the selection.
+/// > The selection consists of everything from the beginning of the starting selector through to the
+/// > beginning of the ending selector, but not including it.
+/// [Web Annotation Data Model - Range Selector](https://www.w3.org/TR/annotation-model/#range-selector)
+///
+/// NOTE: the Hypothesis client doesn't follow this standard, it uses XPaths to the start and end
+/// elements along with character offsets into them instead of nested selectors.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeSelector {
+    /// XPath to the element containing the start of the selection, e.g. "/div[1]/p[2]"
+    pub start_container: String,
+    /// Character offset of the start of the selection within `start_container`
+    pub start_offset: usize,
+    /// XPath to the element containing the end of the selection
+    pub end_container: String,
+    /// Character offset of the end of the selection within `end_container`
+    pub end_offset: usize,
+}
+
+/// > This Selector describes a range of text by recording the start and end positions of the
+/// > selection in the stream. Position 0 would be immediately before the first character,
+/// > position 1 would be immediately before the second character, and so on.
+/// [Web Annotation Data Model - Text Position Selector](https://www.w3.org/TR/annotation-model/#text-position-selector)
+///
+/// The Hypothesis API sometimes returns this selector without positions, they're `None` then
+/// and aren't sent back.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct TextPositionSelector {
+    /// Position of the first selected character
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    /// Position immediately after the last selected character
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>,
+}
+
+/// > This Selector describes a range of data by recording the start and end positions of the
+/// > selection in the stream. Position 0 would be immediately before the first byte,
+/// > position 1 would be immediately before the second byte, and so on.
+/// [Web Annotation Data Model - Data Position Selector](https://www.w3.org/TR/annotation-model/#data-position-selector)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct DataPositionSelector {
+    /// Position of the first selected byte
+    pub start: usize,
+    /// Position immediately after the last selected byte
+    pub end: usize,
+}
+
+/// > A resource can be identified using a fragment identifier (the part of the IRI after the "#"),
+/// > such as a page of a PDF or a region of an image, along with the specification it conforms to.
+/// [Web Annotation Data Model - Fragment Selector](https://www.w3.org/TR/annotation-model/#fragment-selector)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FragmentSelector {
+    /// The fragment identifier, e.g. "page=10" for a PDF
+    pub value: String,
+    /// URL of the specification the fragment conforms to, e.g. "http://tools.ietf.org/rfc/rfc3778"
+    #[serde(rename = "conformsTo", skip_serializing_if = "Option::is_none")]
+    pub conforms_to: Option<String>,
+    /// Any other fields sent with the selector
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// > A CSS Selector describes a Segment of interest in a representation that conforms to the
+/// > Document Object Model through the use of the CSS selector specification.
+/// [Web Annotation Data Model - CSS Selector](https://www.w3.org/TR/annotation-model/#css-selector)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CssSelector {
+    /// The CSS selection path to the Segment
+    pub value: String,
+    /// Selects a part of the selected element, e.g. with a `TextQuoteSelector`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refined_by: Option<Box<Selector>>,
+}
+
+/// > An XPath Selector describes a Segment of interest in a representation that conforms to the
+/// > Document Object Model through the use of the XPath specification.
+/// [Web Annotation Data Model - XPath Selector](https://www.w3.org/TR/annotation-model/#xpath-selector)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct XPathSelector {
+    /// The XPath to the Segment
+    pub value: String,
+    /// Selects a part of the selected element, e.g. with a `TextQuoteSelector`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refined_by: Option<Box<Selector>>,
+}
+
+/// > An SvgSelector defines an area through the use of the Scalable Vector Graphics standard.
+/// [Web Annotation Data Model - SVG Selector](https://www.w3.org/TR/annotation-model/#svg-selector)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SvgSelector {
+    /// Inline SVG document describing the shape, e.g. `<svg:svg> ... </svg:svg>`.
+    /// Empty if the selector refers to an external SVG resource with `id` instead.
+    #[serde(default)]
+    pub value: String,
+    /// Any other fields sent with the selector, e.g. `id`
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Selects a page of a paginated document such as a PDF,
+/// see [hypothesis/h#7803](https://github.com/hypothesis/h/issues/7803)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct PageSelector {
+    /// Page label as shown in the document's viewer, e.g. "iv" or "12"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Zero-based index of the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+}
+
+/// Selects a chapter (content document) of an EPUB, used by the Hypothesis client in e-book readers
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct EPUBContentSelector {
+    /// EPUB Canonical Fragment Identifier of the content document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cfi: Option<String>,
+    /// URL of the content document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Title of the chapter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Selects a time range of an audio or video, used by the Hypothesis client for e.g. video transcripts
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct MediaTimeSelector {
+    /// Start of the selection, in seconds from the start of the media
+    pub start: f64,
+    /// End of the selection, in seconds from the start of the media
+    pub end: f64,
+}
+
 #[cfg_attr(feature = "cli", derive(ValueEnum))]
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -343,10 +800,30 @@ impl Default for Order {
     }
 }
 
+/// Search response of the API, see [`Hypothesis::search_annotations_raw`](crate::Hypothesis::search_annotations_raw)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SearchResult {
+    /// Annotations matching the query, at most `limit`
+    pub rows: Vec<Annotation>,
+    /// Total number of annotations matching the query
+    pub total: usize,
+}
+
+/// One page of search results, see [`Hypothesis::search_annotations_page`](crate::Hypothesis::search_annotations_page)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SearchPage {
+    /// Annotations in this page
+    pub annotations: Vec<Annotation>,
+    /// Pass this to the next call to get the next page, `None` if this is the last page
+    pub next_cursor: Option<String>,
+    /// Total number of annotations matching the query
+    pub total: usize,
+}
+
 /// Options to filter and sort search results. See [the Hypothesis API docs](https://h.readthedocs.io/en/latest/api-reference/v1/#tag/annotations/paths/~1search/get) for more details on using these fields
 #[cfg_attr(feature = "cli", derive(Parser))]
 #[derive(Serialize, Debug, Clone, PartialEq, Builder, Default)]
-#[builder(build_fn(name = "builder"), default)]
+#[builder(build_fn(name = "builder", validate = "Self::validate"), default)]
 pub struct SearchQuery {
     /// The maximum number of annotations to return.
     ///
@@ -396,6 +873,10 @@ pub struct SearchQuery {
     #[builder(setter(into))]
     pub uri_parts: String,
     /// Limit the results to annotations whose URIs match the wildcard pattern.
+    ///
+    /// `*` matches any number of characters and `_` a single character, only in the path of the URI
+    /// (e.g. "https://example.com/*/page_"). Without a scheme (e.g. "example.com/*") the pattern
+    /// is matched against the normalized URIs.
     #[serde(rename = "wildcard_uri", skip_serializing_if = "is_default")]
     #[cfg_attr(feature = "cli", clap(default_value = "", long))]
     #[builder(setter(into))]
@@ -417,9 +898,18 @@ pub struct SearchQuery {
     #[builder(setter(into))]
     pub tag: String,
     /// Similar to tag but allows a list of multiple tags.
+    /// Only annotations tagged with all of these tags are returned, see `tags_any` to match any of them.
     #[serde(skip_serializing_if = "is_default")]
     #[cfg_attr(feature = "cli", clap(long))]
     pub tags: Vec<String>,
+    /// Only keep annotations tagged with at least one of these tags.
+    ///
+    /// The API only supports matching all tags (see `tags`), so this is filtered client-side
+    /// after each page is fetched: searches may return fewer than `limit` annotations,
+    /// and totals (e.g. from `count_annotations`) ignore it.
+    #[serde(skip)]
+    #[cfg_attr(feature = "cli", clap(long))]
+    pub tags_any: Vec<String>,
     /// Limit the results to annotations who contain the indicated keyword in any of the following fields:
     /// `quote`, `tags`, `text`, `url`
     #[serde(skip_serializing_if = "is_default")]
@@ -427,6 +917,9 @@ pub struct SearchQuery {
     #[builder(setter(into))]
     pub any: String,
     /// Limit the results to annotations that contain this text inside the text that was annotated.
+    ///
+    /// The API matches words rather than the exact text, use `Hypothesis::search_annotations_by_quote`
+    /// to only keep annotations quoting this text exactly.
     #[serde(skip_serializing_if = "is_default")]
     #[cfg_attr(feature = "cli", clap(default_value = "", long))]
     #[builder(setter(into))]
@@ -441,15 +934,184 @@ pub struct SearchQuery {
     #[cfg_attr(feature = "cli", clap(default_value = "", long))]
     #[builder(setter(into))]
     pub text: String,
+    /// Limit the results to annotations updated after this date. Example: "2019-01-03T19:46:09.334Z"
+    ///
+    /// The API has no date filters, so this is only used by `search_annotations_return_all` and
+    /// `search_annotations_stream`: they start from `since` (unless `search_after` is set).
+    #[serde(skip)]
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[builder(setter(strip_option))]
+    pub since: Option<DateTime<Utc>>,
+    /// Limit the results to annotations updated before this date. Example: "2019-01-03T19:46:09.334Z"
+    ///
+    /// The API has no date filters, so this is only used by `search_annotations_return_all` and
+    /// `search_annotations_stream`: they stop paginating once an annotation updated after `until` is reached.
+    #[serde(skip)]
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[builder(setter(strip_option))]
+    pub until: Option<DateTime<Utc>>,
+    /// Let `search_annotations_return_all` run a query without any filters,
+    /// which pages through every public annotation
+    #[serde(skip)]
+    #[cfg_attr(feature = "cli", clap(long))]
+    pub allow_unbounded: bool,
+}
+
+impl SearchQuery {
+    /// Sets up the query to page through results with `search_after`, starting from `since`
+    ///
+    /// `search_after` is set to the last annotation's `updated` date after each page,
+    /// which only works when sorting by ascending `updated` date
+    pub(crate) fn prepare_pagination(&mut self) {
+        self.sort = Sort::Updated;
+        self.order = Order::Asc;
+        if let Some(since) = self.since {
+            if self.search_after.is_empty() {
+                self.search_after = since.to_rfc3339();
+            }
+        }
+    }
+
+    /// Whether the annotation has one of the `tags_any` tags, always true if none are given
+    pub(crate) fn matches_tags_any(&self, annotation: &Annotation) -> bool {
+        self.tags_any.is_empty()
+            || annotation
+                .tags
+                .iter()
+                .any(|tag| self.tags_any.contains(tag))
+    }
+
+    /// Value of the annotation's sort field, to continue searching after it with `search_after`
+    pub(crate) fn cursor(&self, annotation: &Annotation) -> String {
+        match self.sort {
+            Sort::Created => annotation.created.to_rfc3339(),
+            Sort::Updated => annotation.updated.to_rfc3339(),
+            Sort::Id => annotation.id.clone(),
+            Sort::Group => annotation.group.clone(),
+            Sort::User => annotation.user.0.clone(),
+        }
+    }
+
+    /// Whether none of the filters narrowing down the results on the API side are set
+    ///
+    /// Filters that are only whitespace are ignored by the API, so they don't count.
+    pub fn is_unbounded(&self) -> bool {
+        let blank = |value: &String| value.trim().is_empty();
+        blank(&self.uri)
+            && blank(&self.uri_parts)
+            && blank(&self.wildcard_uri)
+            && blank(&self.user)
+            && self.group.iter().all(blank)
+            && blank(&self.tag)
+            && self.tags.iter().all(blank)
+            && blank(&self.any)
+            && blank(&self.quote)
+            && blank(&self.references)
+            && blank(&self.text)
+    }
+
+    /// Index of the first annotation updated after `until`, if any
+    pub(crate) fn until_index(&self, annotations: &[Annotation]) -> Option<usize> {
+        let until = self.until?;
+        annotations
+            .iter()
+            .position(|annotation| annotation.updated > until)
+    }
 }
 
 impl SearchQuery {
     pub fn builder() -> SearchQueryBuilder {
         SearchQueryBuilder::default()
     }
+
+    /// Annotations on the page at `url`, i.e. a query with `uri` set
+    ///
+    /// ```
+    /// # use hypothesis::annotations::SearchQuery;
+    /// let query = SearchQuery::for_url("https://example.com/page")?;
+    /// assert_eq!(query.uri, "https://example.com/page");
+    /// # Ok::<(), hypothesis::errors::HypothesisError>(())
+    /// ```
+    pub fn for_url(url: &str) -> Result<Self, errors::HypothesisError> {
+        Self::builder().uri(url).build()
+    }
+
+    /// Annotations on every page whose URL starts with `prefix`, e.g. a whole site,
+    /// using a `wildcard_uri` ending in `*`
+    ///
+    /// ```
+    /// # use hypothesis::annotations::SearchQuery;
+    /// let query = SearchQuery::for_url_prefix("https://example.com")?;
+    /// assert_eq!(query.wildcard_uri, "https://example.com/*");
+    /// let query = SearchQuery::for_url_prefix("https://example.com/blog/")?;
+    /// assert_eq!(query.wildcard_uri, "https://example.com/blog/*");
+    /// # Ok::<(), hypothesis::errors::HypothesisError>(())
+    /// ```
+    pub fn for_url_prefix(prefix: &str) -> Result<Self, errors::HypothesisError> {
+        let mut wildcard_uri = prefix.to_string();
+        if !wildcard_uri.ends_with('*') {
+            // wildcards aren't allowed in the domain, so match all paths of a bare domain
+            if matches!(wildcard_uri.split_once("://"), Some((_, rest)) if !rest.contains('/')) {
+                wildcard_uri.push('/');
+            }
+            wildcard_uri.push('*');
+        }
+        Self::builder().wildcard_uri(wildcard_uri).build()
+    }
 }
 
 impl SearchQueryBuilder {
+    /// Only search annotations in the Public group
+    pub fn public_group(&mut self) -> &mut Self {
+        self.group(vec![PUBLIC_GROUP_ID.to_string()])
+    }
+
+    /// Only match annotations tagged with all of these tags, same as `tags`
+    pub fn tags_all(&mut self, tags: Vec<String>) -> &mut Self {
+        self.tags(tags)
+    }
+
+    /// Checks `limit` and `offset` against the ranges accepted by the API
+    fn validate(&self) -> Result<(), String> {
+        if let Some(limit) = self.limit {
+            if limit > 200 {
+                return Err(format!("`limit` must be in 0..=200, got {}", limit));
+            }
+        }
+        if let Some(offset) = self.offset {
+            if offset > 9800 {
+                return Err(format!("`offset` must be in 0..=9800, got {}", offset));
+            }
+        }
+        if let Some(group) = self
+            .group
+            .iter()
+            .flatten()
+            .find(|group| !is_valid_group_id(group))
+        {
+            return Err(format!(
+                "`group` must be a group ID like \"{}\", got {:?}",
+                PUBLIC_GROUP_ID, group
+            ));
+        }
+        // only the path may contain wildcards, e.g. "https://example.com/*/page_",
+        // patterns without a scheme (e.g. "example.com/*") are matched against normalized URIs
+        if let Some((scheme, rest)) = self
+            .wildcard_uri
+            .as_deref()
+            .and_then(|uri| uri.split_once("://"))
+        {
+            let host = rest.split('/').next().unwrap_or_default();
+            if scheme.contains(['*', '_']) || host.contains(['*', '_']) {
+                return Err(format!(
+                    "`wildcard_uri` can only have wildcards (* or _) in the path, not the scheme or domain, got {:?}",
+                    self.wildcard_uri.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Builds a new `SearchQuery`.
     pub fn build(&self) -> Result<SearchQuery, errors::HypothesisError> {
         self.builder()
@@ -457,10 +1119,31 @@ impl SearchQueryBuilder {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct Permissions {
     pub read: Vec<String>,
     pub delete: Vec<String>,
     pub admin: Vec<String>,
     pub update: Vec<String>,
 }
+
+impl Permissions {
+    /// Readable by members of `group`, only `user` can update and delete it
+    ///
+    /// ```
+    /// # use hypothesis::UserAccountID;
+    /// # use hypothesis::annotations::Permissions;
+    /// let user = UserAccountID::new("username", "hypothes.is");
+    /// let permissions = Permissions::group_readable(&user, "__world__");
+    /// assert_eq!(permissions.read, vec!["group:__world__".to_string()]);
+    /// assert_eq!(permissions.update, vec![user.0]);
+    /// ```
+    pub fn group_readable(user: &UserAccountID, group: &str) -> Self {
+        Self {
+            read: vec![format!("group:{}", group)],
+            delete: vec![user.0.clone()],
+            admin: vec![user.0.clone()],
+            update: vec![user.0.clone()],
+        }
+    }
+}
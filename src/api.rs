@@ -0,0 +1,147 @@
+//! Trait covering the main API calls, to swap [`Hypothesis`] for a mock in tests
+//!
+//! Write code that calls the API against `impl HypothesisApi` (or a generic `A: HypothesisApi`)
+//! and pass it a [`Hypothesis`] client in your application and a mock implementation in your tests.
+//!
+//! # Example
+//! ```
+//! use hypothesis::api::HypothesisApi;
+//! use hypothesis::annotations::SearchQuery;
+//! use hypothesis::errors::HypothesisError;
+//!
+//! /// Counts how many of the user's annotations have a tag
+//! async fn count_tagged(api: &impl HypothesisApi, user: &str, tag: &str) -> Result<usize, HypothesisError> {
+//!     let query = SearchQuery::builder()
+//!         .user(user)
+//!         .tags(vec![tag.to_string()])
+//!         .limit(200)
+//!         .build()?;
+//!     Ok(api.search_annotations(&query).await?.len())
+//! }
+//! ```
+use std::future::Future;
+
+use crate::annotations::{Annotation, InputAnnotation, SearchQuery};
+use crate::errors::HypothesisError;
+use crate::groups::{Expand, Group, GroupFilters};
+use crate::profile::UserProfile;
+use crate::Hypothesis;
+
+/// The main annotation, group and profile calls of [`Hypothesis`].
+///
+/// See the inherent methods of [`Hypothesis`] with the same names for documentation.
+pub trait HypothesisApi {
+    /// See [`Hypothesis::create_annotation`]
+    fn create_annotation(
+        &self,
+        annotation: &InputAnnotation,
+    ) -> impl Future<Output = Result<Annotation, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::update_annotation`]
+    fn update_annotation(
+        &self,
+        annotation: &Annotation,
+    ) -> impl Future<Output = Result<Annotation, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::patch_annotation`]
+    fn patch_annotation(
+        &self,
+        id: &str,
+        changes: &InputAnnotation,
+    ) -> impl Future<Output = Result<Annotation, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::fetch_annotation`]
+    fn fetch_annotation(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Annotation, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::delete_annotation`]
+    fn delete_annotation(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<bool, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::search_annotations`]
+    fn search_annotations(
+        &self,
+        query: &SearchQuery,
+    ) -> impl Future<Output = Result<Vec<Annotation>, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::get_groups`]
+    fn get_groups(
+        &self,
+        query: &GroupFilters,
+    ) -> impl Future<Output = Result<Vec<Group>, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::fetch_group`]
+    fn fetch_group(
+        &self,
+        id: &str,
+        expand: Vec<Expand>,
+    ) -> impl Future<Output = Result<Group, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::fetch_user_profile`]
+    fn fetch_user_profile(
+        &self,
+    ) -> impl Future<Output = Result<UserProfile, HypothesisError>> + Send;
+
+    /// See [`Hypothesis::fetch_user_groups`]
+    fn fetch_user_groups(&self)
+        -> impl Future<Output = Result<Vec<Group>, HypothesisError>> + Send;
+}
+
+impl HypothesisApi for Hypothesis {
+    async fn create_annotation(
+        &self,
+        annotation: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        Hypothesis::create_annotation(self, annotation).await
+    }
+
+    async fn update_annotation(
+        &self,
+        annotation: &Annotation,
+    ) -> Result<Annotation, HypothesisError> {
+        Hypothesis::update_annotation(self, annotation).await
+    }
+
+    async fn patch_annotation(
+        &self,
+        id: &str,
+        changes: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        Hypothesis::patch_annotation(self, id, changes).await
+    }
+
+    async fn fetch_annotation(&self, id: &str) -> Result<Annotation, HypothesisError> {
+        Hypothesis::fetch_annotation(self, id).await
+    }
+
+    async fn delete_annotation(&self, id: &str) -> Result<bool, HypothesisError> {
+        Hypothesis::delete_annotation(self, id).await
+    }
+
+    async fn search_annotations(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        Hypothesis::search_annotations(self, query).await
+    }
+
+    async fn get_groups(&self, query: &GroupFilters) -> Result<Vec<Group>, HypothesisError> {
+        Hypothesis::get_groups(self, query).await
+    }
+
+    async fn fetch_group(&self, id: &str, expand: Vec<Expand>) -> Result<Group, HypothesisError> {
+        Hypothesis::fetch_group(self, id, expand).await
+    }
+
+    async fn fetch_user_profile(&self) -> Result<UserProfile, HypothesisError> {
+        Hypothesis::fetch_user_profile(self).await
+    }
+
+    async fn fetch_user_groups(&self) -> Result<Vec<Group>, HypothesisError> {
+        Hypothesis::fetch_user_groups(self).await
+    }
+}
@@ -0,0 +1,134 @@
+//! A blocking client for scripts without an async runtime, enabled with the `blocking` feature
+//!
+//! Wraps the async [`Hypothesis`](crate::Hypothesis) client and runs each call to completion on
+//! its own single-threaded runtime. Don't use it from async code, calls panic inside a runtime.
+//!
+//! # Example
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use hypothesis::blocking::Hypothesis;
+//! use hypothesis::annotations::SearchQuery;
+//! #     dotenvy::dotenv()?;
+//! #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+//! #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+//! let api = Hypothesis::new(&username, &developer_key)?;
+//! let query = SearchQuery::builder().user(&api.inner().user.0).limit(5).build()?;
+//! let annotations = api.search_annotations(&query)?;
+//! #     assert!(annotations.len() <= 5);
+//! #     Ok(())
+//! # }
+//! ```
+use tokio::runtime::{Builder, Runtime};
+
+use crate::annotations::{Annotation, InputAnnotation, SearchQuery};
+use crate::errors::HypothesisError;
+use crate::groups::{Expand, Group, GroupFilters};
+use crate::profile::UserProfile;
+
+/// Blocking version of [`crate::Hypothesis`], see its methods for documentation
+pub struct Hypothesis {
+    inner: crate::Hypothesis,
+    runtime: Runtime,
+}
+
+impl Hypothesis {
+    /// Make a new blocking client with your username and developer key, see [`crate::Hypothesis::new`]
+    pub fn new(username: &str, developer_key: &str) -> Result<Self, HypothesisError> {
+        Self::from_async(crate::Hypothesis::new(username, developer_key)?)
+    }
+
+    /// Make a new blocking client from environment variables, see [`crate::Hypothesis::from_env`]
+    pub fn from_env() -> Result<Self, HypothesisError> {
+        Self::from_async(crate::Hypothesis::from_env()?)
+    }
+
+    /// Wrap an async client, e.g. one configured with [`crate::Hypothesis::builder`]
+    pub fn from_async(inner: crate::Hypothesis) -> Result<Self, HypothesisError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(HypothesisError::RuntimeError)?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// The wrapped async client
+    pub fn inner(&self) -> &crate::Hypothesis {
+        &self.inner
+    }
+
+    pub fn create_annotation(
+        &self,
+        annotation: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        self.runtime
+            .block_on(self.inner.create_annotation(annotation))
+    }
+
+    pub fn create_annotations(
+        &self,
+        annotations: &[InputAnnotation],
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        self.runtime
+            .block_on(self.inner.create_annotations(annotations))
+    }
+
+    pub fn update_annotation(
+        &self,
+        annotation: &Annotation,
+    ) -> Result<Annotation, HypothesisError> {
+        self.runtime
+            .block_on(self.inner.update_annotation(annotation))
+    }
+
+    pub fn patch_annotation(
+        &self,
+        id: &str,
+        changes: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        self.runtime
+            .block_on(self.inner.patch_annotation(id, changes))
+    }
+
+    pub fn fetch_annotation(&self, id: &str) -> Result<Annotation, HypothesisError> {
+        self.runtime.block_on(self.inner.fetch_annotation(id))
+    }
+
+    pub fn delete_annotation(&self, id: &str) -> Result<bool, HypothesisError> {
+        self.runtime.block_on(self.inner.delete_annotation(id))
+    }
+
+    pub fn delete_annotations(&self, ids: &[String]) -> Result<Vec<bool>, HypothesisError> {
+        self.runtime.block_on(self.inner.delete_annotations(ids))
+    }
+
+    pub fn search_annotations(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        self.runtime.block_on(self.inner.search_annotations(query))
+    }
+
+    pub fn search_annotations_return_all(
+        &self,
+        query: &mut SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        self.runtime
+            .block_on(self.inner.search_annotations_return_all(query))
+    }
+
+    pub fn get_groups(&self, query: &GroupFilters) -> Result<Vec<Group>, HypothesisError> {
+        self.runtime.block_on(self.inner.get_groups(query))
+    }
+
+    pub fn fetch_group(&self, id: &str, expand: Vec<Expand>) -> Result<Group, HypothesisError> {
+        self.runtime.block_on(self.inner.fetch_group(id, expand))
+    }
+
+    pub fn fetch_user_profile(&self) -> Result<UserProfile, HypothesisError> {
+        self.runtime.block_on(self.inner.fetch_user_profile())
+    }
+
+    pub fn fetch_user_groups(&self) -> Result<Vec<Group>, HypothesisError> {
+        self.runtime.block_on(self.inner.fetch_user_groups())
+    }
+}
@@ -0,0 +1,517 @@
+//! Bulk operations on annotations and groups
+//!
+//! Requests are sent concurrently, with at most
+//! [`max_concurrency`](crate::HypothesisBuilder::max_concurrency) in flight at once.
+//! Results are returned in the same order as the input.
+//! The `*_settled` variants keep going after a failed request and return one result per input.
+//! The `*_dry_run` variants of the destructive operations only send read requests and return
+//! what would be changed.
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+use crate::annotations::{Annotation, InputAnnotation, SearchQuery};
+use crate::errors::HypothesisError;
+use crate::groups::{Expand, Group, PUBLIC_GROUP_ID};
+use crate::Hypothesis;
+
+impl Hypothesis {
+    /// Create many new annotations
+    ///
+    /// Posts multiple new annotation objects asynchronously to Hypothesis.
+    /// Returns [`Annotation`](annotations/struct.Annotation.html)s as output.
+    /// See [`InputAnnotation`'s](annotations/struct.InputAnnotation.html) docs for examples on what
+    /// you can add to an annotation.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use hypothesis::Hypothesis;
+    /// # use hypothesis::annotations::InputAnnotation;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let input_annotations = vec![
+    ///     InputAnnotation::builder()
+    ///         .text("first")
+    ///         .uri("http://example.com")
+    ///         .group(&group_id)
+    ///         .build()?,
+    ///     InputAnnotation::builder()
+    ///         .text("second")
+    ///         .uri("http://example.com")
+    ///         .group(&group_id)   
+    ///         .build()?
+    /// ];
+    /// let annotations = api.create_annotations(&input_annotations).await?;
+    /// assert_eq!(&annotations[0].text, "first");
+    /// assert_eq!(&annotations[1].text, "second");
+    /// #    api.delete_annotations(&annotations.into_iter().map(|a| a.id).collect::<Vec<_>>()).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn create_annotations(
+        &self,
+        annotations: &[InputAnnotation],
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        stream::iter(annotations.iter())
+            .map(|a| self.create_annotation(a))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Update many annotations at once
+    pub async fn update_annotations(
+        &self,
+        annotations: &[Annotation],
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        stream::iter(annotations.iter())
+            .map(|a| self.update_annotation(a))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Check what `update_annotations` would do, without updating anything
+    ///
+    /// Returns the IDs of the annotations that exist and would be updated, in the same order.
+    pub async fn update_annotations_dry_run(
+        &self,
+        annotations: &[Annotation],
+    ) -> Result<Vec<String>, HypothesisError> {
+        self.existing_annotation_ids(annotations.iter().map(|a| &a.id))
+            .await
+    }
+
+    /// Update only some fields of many annotations, given as pairs of annotation ID and changes
+    pub async fn patch_annotations(
+        &self,
+        changes: &[(String, InputAnnotation)],
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        stream::iter(changes.iter())
+            .map(|(id, changes)| self.patch_annotation(id, changes))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Check what `patch_annotations` would do, without updating anything
+    ///
+    /// Returns the IDs of the annotations that exist and would be updated, in the same order.
+    pub async fn patch_annotations_dry_run(
+        &self,
+        changes: &[(String, InputAnnotation)],
+    ) -> Result<Vec<String>, HypothesisError> {
+        self.existing_annotation_ids(changes.iter().map(|(id, _)| id))
+            .await
+    }
+
+    /// Fetch multiple annotations by ID
+    ///
+    /// The API has no endpoint to fetch several annotations in one request (and search can't
+    /// filter by ID), so this makes one request per ID, `max_concurrency` at a time.
+    /// Raise [`max_concurrency`](crate::HypothesisBuilder::max_concurrency) to fetch many annotations faster.
+    pub async fn fetch_annotations(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        stream::iter(ids.iter())
+            .map(|id| self.fetch_annotation(id))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Move multiple annotations by ID to another group, see [`Hypothesis::move_annotation`]
+    pub async fn move_annotations(
+        &self,
+        ids: &[String],
+        to_group: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        stream::iter(ids.iter())
+            .map(|id| self.move_annotation(id, to_group))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Check what `move_annotations` would do, without moving anything
+    ///
+    /// Returns the annotations that aren't in `to_group` yet and would be copied there
+    /// (and deleted from their current group), in the same order.
+    pub async fn move_annotations_dry_run(
+        &self,
+        ids: &[String],
+        to_group: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let mut annotations = self.fetch_annotations(ids).await?;
+        annotations.retain(|annotation| annotation.group != to_group);
+        Ok(annotations)
+    }
+
+    /// Delete multiple annotations by ID
+    pub async fn delete_annotations(&self, ids: &[String]) -> Result<Vec<bool>, HypothesisError> {
+        stream::iter(ids.iter())
+            .map(|id| self.delete_annotation(id))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Check what `delete_annotations` would do, without deleting anything
+    ///
+    /// Returns the IDs of the annotations that exist and would be deleted, in the same order.
+    /// Deleting can still fail if the user isn't allowed to delete some of them.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let query = SearchQuery::builder().user(&api.user.0).tags(vec!["to-delete".into()]).build()?;
+    /// let ids: Vec<_> = api.search_annotations(&query).await?.into_iter().map(|a| a.id).collect();
+    /// let to_delete = api.delete_annotations_dry_run(&ids).await?;
+    /// println!("Would delete {} annotations: {:?}", to_delete.len(), to_delete);
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn delete_annotations_dry_run(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<String>, HypothesisError> {
+        self.existing_annotation_ids(ids).await
+    }
+
+    /// IDs of the annotations that exist, in the same order
+    async fn existing_annotation_ids<'a>(
+        &self,
+        ids: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Vec<String>, HypothesisError> {
+        let ids: Vec<_> = ids.into_iter().collect();
+        let exists: Vec<bool> = stream::iter(ids.iter())
+            .map(|id| self.annotation_exists(id))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await?;
+        Ok(ids
+            .into_iter()
+            .zip(exists)
+            .filter_map(|(id, exists)| exists.then(|| id.clone()))
+            .collect())
+    }
+
+    /// Add a tag to every annotation matching a search query
+    ///
+    /// Retrieves all matches like `search_annotations_return_all` (so the query needs a filter,
+    /// see [`SearchQuery::allow_unbounded`]) and updates the tags of the annotations that don't
+    /// have `tag` yet. Returns all matching annotations, with their updated tags.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::{InputAnnotation, SearchQuery};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// # let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #     .text("to curate")
+    /// #     .uri("https://www.example.com")
+    /// #     .group(&group_id)
+    /// #     .build()?).await?;
+    /// let query = SearchQuery::builder()
+    ///     .user(&api.user.0)
+    ///     .uri("https://www.example.com")
+    ///     .build()?;
+    /// let annotations = api.tag_search_results(&query, "curated").await?;
+    /// assert!(annotations.iter().all(|annotation| annotation.tags.contains(&"curated".to_string())));
+    /// #     api.delete_annotation(&annotation.id).await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn tag_search_results(
+        &self,
+        query: &SearchQuery,
+        tag: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let annotations = self
+            .search_annotations_return_all(&mut query.clone())
+            .await?;
+        stream::iter(annotations)
+            .map(|annotation| async move {
+                if annotation.tags.iter().any(|t| t == tag) {
+                    return Ok(annotation);
+                }
+                let mut tags = annotation.tags;
+                tags.push(tag.to_string());
+                self.patch_tags(&annotation.id, tags).await
+            })
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Check what `tag_search_results` would do, without updating anything
+    ///
+    /// Returns the matching annotations that don't have `tag` yet and would be updated.
+    pub async fn tag_search_results_dry_run(
+        &self,
+        query: &SearchQuery,
+        tag: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let mut annotations = self
+            .search_annotations_return_all(&mut query.clone())
+            .await?;
+        annotations.retain(|annotation| !annotation.tags.iter().any(|t| t == tag));
+        Ok(annotations)
+    }
+
+    /// Create multiple groups
+    pub async fn create_groups(
+        &self,
+        names: &[String],
+        descriptions: &[Option<String>],
+    ) -> Result<Vec<Group>, HypothesisError> {
+        stream::iter(names.iter().zip(descriptions.iter()))
+            .map(|(name, description)| self.create_group(name, description.as_deref()))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Fetch multiple groups by ID
+    pub async fn fetch_groups(
+        &self,
+        ids: &[String],
+        expands: Vec<Vec<Expand>>,
+    ) -> Result<Vec<Group>, HypothesisError> {
+        stream::iter(ids.iter().zip(expands))
+            .map(|(id, expand)| self.fetch_group(id, expand))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Update multiple groups
+    pub async fn update_groups(
+        &self,
+        ids: &[String],
+        names: &[Option<String>],
+        descriptions: &[Option<String>],
+    ) -> Result<Vec<Group>, HypothesisError> {
+        stream::iter(ids.iter().zip(names.iter()).zip(descriptions.iter()))
+            .map(|((id, name), description)| {
+                self.update_group(id, name.as_deref(), description.as_deref())
+            })
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Leave multiple groups
+    pub async fn leave_groups(&self, ids: &[String]) -> Result<(), HypothesisError> {
+        stream::iter(ids.iter())
+            .map(|id| self.leave_group(id))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Check what `leave_groups` would do, without leaving any group
+    ///
+    /// Returns the IDs of the user's groups among `ids`, in the same order.
+    /// The public group can't be left so it's never included.
+    pub async fn leave_groups_dry_run(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<String>, HypothesisError> {
+        let groups = self.fetch_user_groups().await?;
+        Ok(ids
+            .iter()
+            .filter(|id| {
+                id.as_str() != PUBLIC_GROUP_ID && groups.iter().any(|group| group.id == **id)
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Leave all of the user's groups for which `predicate` returns true, e.g. to clean up after tests.
+    /// The public group can't be left so it's skipped.
+    ///
+    /// Returns the groups that were left.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let left = api.leave_groups_matching(|group| group.name.starts_with("test_")).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn leave_groups_matching<F>(
+        &self,
+        predicate: F,
+    ) -> Result<Vec<Group>, HypothesisError>
+    where
+        F: Fn(&Group) -> bool,
+    {
+        let groups = self.leave_groups_matching_dry_run(predicate).await?;
+        let ids: Vec<_> = groups.iter().map(|group| group.id.clone()).collect();
+        self.leave_groups(&ids).await?;
+        Ok(groups)
+    }
+
+    /// Check what `leave_groups_matching` would do, without leaving any group
+    ///
+    /// Returns the groups that would be left.
+    pub async fn leave_groups_matching_dry_run<F>(
+        &self,
+        predicate: F,
+    ) -> Result<Vec<Group>, HypothesisError>
+    where
+        F: Fn(&Group) -> bool,
+    {
+        let mut groups = self.fetch_user_groups().await?;
+        groups.retain(|group| group.id != PUBLIC_GROUP_ID && predicate(group));
+        Ok(groups)
+    }
+
+    /// Create many new annotations, without stopping at the first error
+    ///
+    /// Returns one result per input annotation, in the same order,
+    /// so that only the failed ones need to be retried.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use hypothesis::Hypothesis;
+    /// # use hypothesis::annotations::InputAnnotation;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let input_annotations = vec![
+    ///     InputAnnotation::builder()
+    ///         .text("first")
+    ///         .uri("http://example.com")
+    ///         .group(&group_id)
+    ///         .build()?,
+    ///     InputAnnotation::builder()
+    ///         .text("second")
+    ///         .uri("http://example.com")
+    ///         .group("not_a_group")
+    ///         .build()?
+    /// ];
+    /// let results = api.create_annotations_settled(&input_annotations).await;
+    /// assert_eq!(&results[0].as_ref().unwrap().text, "first");
+    /// assert!(results[1].is_err());
+    /// #    let created: Vec<_> = results.into_iter().flatten().map(|a| a.id).collect();
+    /// #    api.delete_annotations(&created).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn create_annotations_settled(
+        &self,
+        annotations: &[InputAnnotation],
+    ) -> Vec<Result<Annotation, HypothesisError>> {
+        stream::iter(annotations.iter())
+            .map(|a| self.create_annotation(a))
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Update many annotations, returning one result per input annotation in the same order
+    pub async fn update_annotations_settled(
+        &self,
+        annotations: &[Annotation],
+    ) -> Vec<Result<Annotation, HypothesisError>> {
+        stream::iter(annotations.iter())
+            .map(|a| self.update_annotation(a))
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Fetch multiple annotations by ID, returning one result per ID in the same order
+    pub async fn fetch_annotations_settled(
+        &self,
+        ids: &[String],
+    ) -> Vec<Result<Annotation, HypothesisError>> {
+        stream::iter(ids.iter())
+            .map(|id| self.fetch_annotation(id))
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Delete multiple annotations by ID, returning one result per ID in the same order
+    pub async fn delete_annotations_settled(
+        &self,
+        ids: &[String],
+    ) -> Vec<Result<bool, HypothesisError>> {
+        stream::iter(ids.iter())
+            .map(|id| self.delete_annotation(id))
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Create multiple groups, returning one result per name in the same order
+    pub async fn create_groups_settled(
+        &self,
+        names: &[String],
+        descriptions: &[Option<String>],
+    ) -> Vec<Result<Group, HypothesisError>> {
+        stream::iter(names.iter().zip(descriptions.iter()))
+            .map(|(name, description)| self.create_group(name, description.as_deref()))
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Fetch multiple groups by ID, returning one result per ID in the same order
+    pub async fn fetch_groups_settled(
+        &self,
+        ids: &[String],
+        expands: Vec<Vec<Expand>>,
+    ) -> Vec<Result<Group, HypothesisError>> {
+        stream::iter(ids.iter().zip(expands))
+            .map(|(id, expand)| self.fetch_group(id, expand))
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Update multiple groups, returning one result per ID in the same order
+    pub async fn update_groups_settled(
+        &self,
+        ids: &[String],
+        names: &[Option<String>],
+        descriptions: &[Option<String>],
+    ) -> Vec<Result<Group, HypothesisError>> {
+        stream::iter(ids.iter().zip(names.iter()).zip(descriptions.iter()))
+            .map(|((id, name), description)| {
+                self.update_group(id, name.as_deref(), description.as_deref())
+            })
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+}
@@ -1,17 +1,19 @@
 //! Objects related to the command-line tool
-use crate::annotations::InputAnnotation;
+use crate::annotations::{Annotation, InputAnnotation, ModerationAction, Selector, Target};
 use crate::annotations::{Order, SearchQuery, Sort};
-use crate::errors::CLIError;
-use crate::groups::{Expand, GroupFilters};
-use crate::Hypothesis;
+use crate::errors::{CLIError, HypothesisError};
+use crate::groups::{Expand, Group, GroupFilters, Member, Type};
+use crate::{Hypothesis, API_URL};
 
 use clap::CommandFactory;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use clap_complete::Shell;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{fs, io};
+use std::{env, fs, io};
 
 #[derive(Debug, Parser)]
 #[clap(
@@ -36,6 +38,11 @@ pub enum HypothesisCLI {
         cmd: ProfileCommand,
     },
 
+    /// Save your username and developer key to the config file.
+    ///
+    /// The CLI uses them when $HYPOTHESIS_NAME or $HYPOTHESIS_KEY aren't set.
+    Login,
+
     /// Generate shell completions
     Complete {
         #[clap(value_enum)]
@@ -45,13 +52,35 @@ pub enum HypothesisCLI {
 
 #[derive(Parser, Debug)]
 pub enum AnnotationsCommand {
-    /// Create a new annotation (TODO: add Target somehow)
+    /// Create a new annotation
     Create {
         #[clap(flatten)]
         annotation: InputAnnotation,
-        /// write created annotation to this file in JSON format
+        /// exact text in the document to highlight
+        #[clap(long)]
+        exact: Option<String>,
+        /// text immediately before the highlighted text
+        #[clap(long, requires = "exact")]
+        prefix: Option<String>,
+        /// text immediately after the highlighted text
+        #[clap(long, requires = "exact")]
+        suffix: Option<String>,
+        /// URI of the document containing the highlighted text, defaults to the annotation's URI
+        #[clap(long, requires = "exact")]
+        source: Option<String>,
+        /// read the annotation from this JSON file ("-" for stdin) instead of the other arguments
+        #[clap(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["uri", "text", "tags", "group", "references", "exact", "prefix", "suffix", "source"]
+        )]
+        from_json: Option<PathBuf>,
+        /// write created annotation to this file
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// format of the file written with `-o`
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
 
     /// Update an existing annotation
@@ -60,26 +89,39 @@ pub enum AnnotationsCommand {
         id: String,
         #[clap(flatten)]
         annotation: InputAnnotation,
-        /// write updated annotation to this file in JSON format
+        /// write updated annotation to this file
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// format of the file written with `-o`
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
 
     /// Search for annotations with optional filters
     Search {
         #[clap(flatten)]
         query: SearchQuery,
-        /// json file to write search results to, writes to stdout if not given
+        /// file to write search results to, writes to stdout if not given
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
+        /// page through and return all matching annotations, `--limit` sets the page size.
+        /// Results are sorted by ascending update time, ignoring `--sort` and `--order`
+        #[clap(long)]
+        return_all: bool,
     },
     /// Fetch annotation by ID
     Fetch {
         /// unique ID of the annotation to fetch
         id: String,
-        /// json file to write annotation to, writes to stdout if not given
+        /// file to write annotation to, writes to stdout if not given
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
     /// Delete annotation by ID
     Delete {
@@ -111,6 +153,25 @@ pub enum AnnotationsCommand {
         /// unique ID of the annotation to show
         id: String,
     },
+    /// Import annotations exported with `search -o`, e.g. to copy them to another group
+    ///
+    /// Reads one JSON annotation per line (`--format ndjson`) or a JSON array of annotations
+    /// (`--format pretty`) and re-creates them, with the authenticated user as their creator.
+    Import {
+        /// NDJSON or JSON file of annotations to import ("-" for stdin)
+        file: PathBuf,
+        /// group to create the annotations in, keeps each annotation's group if not given
+        #[clap(long)]
+        group: Option<String>,
+    },
+    /// Hide, show or flag an annotation
+    Moderate {
+        /// unique ID of the annotation to moderate
+        id: String,
+        /// moderation action to take
+        #[clap(long, value_enum)]
+        action: ModerationAction,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -120,9 +181,12 @@ pub enum GroupsCommand {
     List {
         #[clap(flatten)]
         filters: GroupFilters,
-        /// json file to write filtered groups to, writes to stdout if not given
+        /// file to write filtered groups to, writes to stdout if not given
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
     /// Create a new, private group for the currently-authenticated user.
     Create {
@@ -141,9 +205,12 @@ pub enum GroupsCommand {
         /// Expand the organization, scope, or both
         #[clap(long, short)]
         expand: Vec<Expand>,
-        /// write group to this file in JSON format
+        /// file to write group to, writes to stdout if not given
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
     /// Update a Group resource.
     Update {
@@ -167,12 +234,34 @@ pub enum GroupsCommand {
     Members {
         /// unique Group ID
         id: String,
-        /// json file to write groups members to, writes to stdout if not given
+        /// file to write groups members to, writes to stdout if not given
+        #[clap(short = 'o', long)]
+        file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
+    },
+    /// Export all annotations in a group, e.g. as a backup.
+    ///
+    /// Annotations are sorted by ascending update time, use `annotations import` to re-create them
+    /// from `ndjson` or `pretty` exports.
+    Export {
+        /// unique Group ID
+        id: String,
+        /// file to write annotations to, writes to stdout if not given
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
     /// Remove yourself from a group.
     Leave { id: String },
+    /// Delete a group. Only the group's creator can delete it.
+    Delete {
+        /// unique Group ID
+        id: String,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -185,62 +274,151 @@ pub enum ProfileCommand {
     },
     /// Fetch the groups for which the currently-authenticated user is a member.
     Groups {
-        /// json file to write groups to, writes to stdout if not given
+        /// Expand the organization, scope, or both
+        #[clap(long, short)]
+        expand: Vec<Expand>,
+        /// file to write groups to, writes to stdout if not given
         #[clap(short = 'o', long)]
         file: Option<PathBuf>,
+        /// output format
+        #[clap(long, value_enum, default_value_t = OutputFormat::Ndjson)]
+        format: OutputFormat,
     },
 }
 
+/// Wraps the error of a failed request for the `kind` (e.g. "Annotation") with ID `id` in a
+/// [`CLIError`] that's shown first, saying "not found" for 404s
+fn request_error<'a>(
+    kind: &'a str,
+    id: &'a str,
+) -> impl FnOnce(HypothesisError) -> color_eyre::Report + 'a {
+    move |error| {
+        let cli_error = CLIError::from_request(&error, kind, id);
+        color_eyre::Report::new(error).wrap_err(cli_error)
+    }
+}
+
 impl HypothesisCLI {
     pub async fn run(self, client: Hypothesis) -> color_eyre::Result<()> {
         match self {
             Self::Annotations { cmd } => match cmd {
-                AnnotationsCommand::Create { annotation, file } => {
+                AnnotationsCommand::Create {
+                    mut annotation,
+                    exact,
+                    prefix,
+                    suffix,
+                    source,
+                    from_json,
+                    file,
+                    format,
+                } => {
+                    if let Some(from_json) = from_json {
+                        let reader: Box<dyn io::Read> = if from_json == Path::new("-") {
+                            Box::new(io::stdin())
+                        } else {
+                            Box::new(fs::File::open(from_json)?)
+                        };
+                        annotation = serde_json::from_reader(io::BufReader::new(reader))?;
+                    }
+                    if let Some(exact) = exact {
+                        annotation.targets.push(Target {
+                            source: source.unwrap_or_else(|| annotation.uri.clone()),
+                            selector: vec![Selector::new_quote(
+                                &exact,
+                                prefix.as_deref().unwrap_or_default(),
+                                suffix.as_deref().unwrap_or_default(),
+                            )],
+                        });
+                    }
                     let annotation = client.create_annotation(&annotation).await?;
                     println!("Created annotation {}", annotation.id);
                     if let Some(file) = file {
-                        let writer: Box<dyn io::Write> = Box::new(fs::File::create(file)?);
-                        let mut buffered = io::BufWriter::new(writer);
-                        writeln!(buffered, "{}", serde_json::to_string(&annotation)?)?;
+                        let writer = io::BufWriter::new(fs::File::create(file)?);
+                        let mut output = RowWriter::one::<Annotation>(writer, format)?;
+                        output.write(&annotation)?;
+                        output.finish()?;
                     }
                 }
                 AnnotationsCommand::Update {
                     id,
                     annotation,
                     file,
+                    format,
                 } => {
-                    let mut old_annotation = client.fetch_annotation(&id).await?;
+                    let mut old_annotation = client
+                        .fetch_annotation(&id)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
                     old_annotation.update(annotation);
                     let annotation = client.update_annotation(&old_annotation).await?;
                     println!("Updated annotation {}", annotation.id);
                     if let Some(file) = file {
-                        let writer: Box<dyn io::Write> = Box::new(fs::File::create(file)?);
-                        let mut buffered = io::BufWriter::new(writer);
-                        writeln!(buffered, "{}", serde_json::to_string(&annotation)?)?;
+                        let writer = io::BufWriter::new(fs::File::create(file)?);
+                        let mut output = RowWriter::one::<Annotation>(writer, format)?;
+                        output.write(&annotation)?;
+                        output.finish()?;
                     }
                 }
-                AnnotationsCommand::Search { query, file } => {
-                    let annotations = client.search_annotations(&query).await?;
+                AnnotationsCommand::Search {
+                    mut query,
+                    file,
+                    format,
+                    return_all,
+                } => {
                     let writer: Box<dyn io::Write> = match file {
                         Some(file) => Box::new(fs::File::create(file)?),
                         None => Box::new(io::stdout()),
                     };
-                    let mut buffered = io::BufWriter::new(writer);
-                    for annotation in annotations {
-                        writeln!(buffered, "{}", serde_json::to_string(&annotation)?)?;
+                    let mut output =
+                        RowWriter::many::<Annotation>(io::BufWriter::new(writer), format)?;
+                    if return_all {
+                        if query.sort != Sort::Updated {
+                            eprintln!("Ignoring --sort, --return-all sorts by update time");
+                        }
+                        if query.offset != 0 {
+                            eprintln!("Ignoring --offset, --return-all pages with --search-after");
+                            query.offset = 0;
+                        }
+                        if query.limit != 20 {
+                            eprintln!(
+                                "--return-all returns all matching annotations, using --limit {} as the page size",
+                                query.limit
+                            );
+                        }
+                        query.sort = Sort::Updated;
+                        query.order = Order::Asc;
+                        let annotations = client.search_annotations_stream(query);
+                        futures::pin_mut!(annotations);
+                        while let Some(annotation) = annotations.try_next().await? {
+                            output.write(&annotation)?;
+                        }
+                    } else {
+                        let annotations = client.search_annotations(&query).await?;
+                        for annotation in annotations {
+                            output.write(&annotation)?;
+                        }
                     }
+                    output.finish()?;
                 }
-                AnnotationsCommand::Fetch { id, file } => {
-                    let annotation = client.fetch_annotation(&id).await?;
+                AnnotationsCommand::Fetch { id, file, format } => {
+                    let annotation = client
+                        .fetch_annotation(&id)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
                     let writer: Box<dyn io::Write> = match file {
                         Some(file) => Box::new(fs::File::create(file)?),
                         None => Box::new(io::stdout()),
                     };
-                    let mut buffered = io::BufWriter::new(writer);
-                    writeln!(buffered, "{}", serde_json::to_string(&annotation)?)?;
+                    let mut output =
+                        RowWriter::one::<Annotation>(io::BufWriter::new(writer), format)?;
+                    output.write(&annotation)?;
+                    output.finish()?;
                 }
                 AnnotationsCommand::Delete { id } => {
-                    let deleted = client.delete_annotation(&id).await?;
+                    let deleted = client
+                        .delete_annotation(&id)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
                     if deleted {
                         println!("Deleted annotation {}", id);
                     } else {
@@ -248,29 +426,101 @@ impl HypothesisCLI {
                     }
                 }
                 AnnotationsCommand::Flag { id } => {
-                    client.flag_annotation(&id).await?;
+                    client
+                        .flag_annotation(&id)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
                     println!("Flagged annotation {}", id);
                 }
                 AnnotationsCommand::Hide { id } => {
-                    client.hide_annotation(&id).await?;
+                    client
+                        .hide_annotation(&id)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
                     println!("Hid annotation {}", id);
                 }
                 AnnotationsCommand::Show { id } => {
-                    client.show_annotation(&id).await?;
+                    client
+                        .show_annotation(&id)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
                     println!("Unhid annotation {}", id);
                 }
+                AnnotationsCommand::Import { file, group } => {
+                    let text = if file == Path::new("-") {
+                        io::read_to_string(io::stdin())?
+                    } else {
+                        fs::read_to_string(file)?
+                    };
+                    // `--format pretty` writes a JSON array, `ndjson` one annotation per line
+                    let annotations: Vec<Annotation> = if text.trim_start().starts_with('[') {
+                        serde_json::from_str(&text)?
+                    } else {
+                        serde_json::Deserializer::from_str(&text)
+                            .into_iter::<Annotation>()
+                            .collect::<Result<_, _>>()?
+                    };
+                    let inputs: Vec<_> = annotations
+                        .iter()
+                        .map(|annotation| annotation.to_input(group.as_deref()))
+                        .collect();
+                    let results = client.create_annotations_settled(&inputs).await;
+                    let mut failed = 0;
+                    for (annotation, result) in annotations.iter().zip(results) {
+                        match result {
+                            Ok(created) => {
+                                println!("Imported annotation {} as {}", annotation.id, created.id)
+                            }
+                            Err(error) => {
+                                failed += 1;
+                                eprintln!(
+                                    "Couldn't import annotation {}: {}",
+                                    annotation.id, error
+                                );
+                            }
+                        }
+                    }
+                    println!(
+                        "Imported {} of {} annotations",
+                        annotations.len() - failed,
+                        annotations.len()
+                    );
+                    if failed > 0 {
+                        return Err(CLIError::ImportError {
+                            failed,
+                            total: annotations.len(),
+                        }
+                        .into());
+                    }
+                }
+                AnnotationsCommand::Moderate { id, action } => {
+                    client
+                        .moderate_annotation(&id, action)
+                        .await
+                        .map_err(request_error("Annotation", &id))?;
+                    match action {
+                        ModerationAction::Hide => println!("Hid annotation {}", id),
+                        ModerationAction::Show => println!("Unhid annotation {}", id),
+                        ModerationAction::Flag => println!("Flagged annotation {}", id),
+                    }
+                }
             },
             Self::Groups { cmd } => match cmd {
-                GroupsCommand::List { filters, file } => {
+                GroupsCommand::List {
+                    filters,
+                    file,
+                    format,
+                } => {
                     let groups = client.get_groups(&filters).await?;
                     let writer: Box<dyn io::Write> = match file {
                         Some(file) => Box::new(fs::File::create(file)?),
                         None => Box::new(io::stdout()),
                     };
-                    let mut buffered = io::BufWriter::new(writer);
+                    let mut output = RowWriter::many::<Group>(io::BufWriter::new(writer), format)?;
                     for group in groups {
-                        writeln!(buffered, "{}", serde_json::to_string(&group)?)?;
+                        output.write(&group)?;
                     }
+                    output.finish()?;
                 }
                 GroupsCommand::Create {
                     name,
@@ -285,14 +535,23 @@ impl HypothesisCLI {
                         writeln!(buffered, "{}", serde_json::to_string(&group)?)?;
                     }
                 }
-                GroupsCommand::Fetch { id, expand, file } => {
-                    let group = client.fetch_group(&id, expand).await?;
+                GroupsCommand::Fetch {
+                    id,
+                    expand,
+                    file,
+                    format,
+                } => {
+                    let group = client
+                        .fetch_group(&id, expand)
+                        .await
+                        .map_err(request_error("Group", &id))?;
                     let writer: Box<dyn io::Write> = match file {
                         Some(file) => Box::new(fs::File::create(file)?),
                         None => Box::new(io::stdout()),
                     };
-                    let mut buffered = io::BufWriter::new(writer);
-                    writeln!(buffered, "{}", serde_json::to_string(&group)?)?;
+                    let mut output = RowWriter::one::<Group>(io::BufWriter::new(writer), format)?;
+                    output.write(&group)?;
+                    output.finish()?;
                 }
                 GroupsCommand::Update {
                     id,
@@ -302,7 +561,8 @@ impl HypothesisCLI {
                 } => {
                     let group = client
                         .update_group(&id, name.as_deref(), description.as_deref())
-                        .await?;
+                        .await
+                        .map_err(request_error("Group", &id))?;
                     println!("Updated group {}", group.id);
                     if let Some(file) = file {
                         let writer: Box<dyn io::Write> = Box::new(fs::File::create(file)?);
@@ -310,21 +570,64 @@ impl HypothesisCLI {
                         writeln!(buffered, "{}", serde_json::to_string(&group)?)?;
                     }
                 }
-                GroupsCommand::Members { id, file } => {
-                    let members = client.get_group_members(&id).await?;
+                GroupsCommand::Members { id, file, format } => {
+                    let members = client
+                        .get_group_members(&id)
+                        .await
+                        .map_err(request_error("Group", &id))?;
                     let writer: Box<dyn io::Write> = match file {
                         Some(file) => Box::new(fs::File::create(file)?),
                         None => Box::new(io::stdout()),
                     };
-                    let mut buffered = io::BufWriter::new(writer);
+                    let mut output = RowWriter::many::<Member>(io::BufWriter::new(writer), format)?;
                     for member in members {
-                        writeln!(buffered, "{}", serde_json::to_string(&member)?)?;
+                        output.write(&member)?;
                     }
+                    output.finish()?;
+                }
+                GroupsCommand::Export { id, file, format } => {
+                    if format == OutputFormat::Csv {
+                        eprintln!("CSV exports can't be re-imported with `annotations import`, use --format ndjson or pretty for that");
+                    }
+                    let writer: Box<dyn io::Write> = match file {
+                        Some(file) => Box::new(fs::File::create(file)?),
+                        None => Box::new(io::stdout()),
+                    };
+                    let mut output =
+                        RowWriter::many::<Annotation>(io::BufWriter::new(writer), format)?;
+                    let query = SearchQuery::builder()
+                        .group(vec![id.clone()])
+                        .limit(200)
+                        .build()?;
+                    let annotations = client.search_annotations_stream(query);
+                    futures::pin_mut!(annotations);
+                    let mut count = 0;
+                    while let Some(annotation) = annotations.try_next().await? {
+                        output.write(&annotation)?;
+                        count += 1;
+                        eprint!("\rExported {} annotations", count);
+                    }
+                    output.finish()?;
+                    eprintln!("\rExported {} annotations from group {}", count, id);
                 }
                 GroupsCommand::Leave { id } => {
-                    client.leave_group(&id).await?;
+                    client
+                        .leave_group(&id)
+                        .await
+                        .map_err(request_error("Group", &id))?;
                     println!("Left group {}", id);
                 }
+                GroupsCommand::Delete { id } => {
+                    let deleted = client
+                        .delete_group(&id)
+                        .await
+                        .map_err(request_error("Group", &id))?;
+                    if deleted {
+                        println!("Deleted group {}", id);
+                    } else {
+                        println!("Couldn't delete group {}", id);
+                    }
+                }
             },
             Self::Profile { cmd } => match cmd {
                 ProfileCommand::User { file } => {
@@ -336,18 +639,24 @@ impl HypothesisCLI {
                     let mut buffered = io::BufWriter::new(writer);
                     writeln!(buffered, "{}", serde_json::to_string(&profile)?)?;
                 }
-                ProfileCommand::Groups { file } => {
-                    let groups = client.fetch_user_groups().await?;
+                ProfileCommand::Groups {
+                    expand,
+                    file,
+                    format,
+                } => {
+                    let groups = client.fetch_user_groups_expanded(expand).await?;
                     let writer: Box<dyn io::Write> = match file {
                         Some(file) => Box::new(fs::File::create(file)?),
                         None => Box::new(io::stdout()),
                     };
-                    let mut buffered = io::BufWriter::new(writer);
+                    let mut output = RowWriter::many::<Group>(io::BufWriter::new(writer), format)?;
                     for group in groups {
-                        writeln!(buffered, "{}", serde_json::to_string(&group)?)?;
+                        output.write(&group)?;
                     }
+                    output.finish()?;
                 }
             },
+            Self::Login => login()?,
             Self::Complete { shell } => {
                 // Generates shell completions
                 let mut cmd = HypothesisCLI::command();
@@ -358,6 +667,237 @@ impl HypothesisCLI {
     }
 }
 
+/// Credentials saved by `hypothesis login`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Config {
+    pub username: String,
+    pub developer_key: String,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/hypothesis/config.json`, defaulting to `~/.config` (or `%APPDATA%` on Windows)
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+        Some(config_dir.join("hypothesis").join("config.json"))
+    }
+
+    /// Reads the saved credentials, `None` if there aren't any
+    pub fn load() -> color_eyre::Result<Option<Self>> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        Ok(Some(serde_json::from_reader(io::BufReader::new(
+            fs::File::open(path)?,
+        ))?))
+    }
+
+    /// Writes the credentials to the config file, only readable by the current user on Unix
+    pub fn save(&self) -> color_eyre::Result<PathBuf> {
+        let path = Self::path().ok_or(CLIError::ConfigError)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+        serde_json::to_writer_pretty(&mut file, self)?;
+        Ok(path)
+    }
+}
+
+/// Prompts for the username and developer key (without echoing it) and saves them with [`Config::save`]
+pub fn login() -> color_eyre::Result<()> {
+    print!("Username: ");
+    io::stdout().flush()?;
+    let mut username = String::new();
+    io::stdin().read_line(&mut username)?;
+    let developer_key =
+        rpassword::prompt_password("Developer key (from https://hypothes.is/account/developer): ")?;
+    let config = Config {
+        username: username.trim().into(),
+        developer_key: developer_key.trim().into(),
+    };
+    // checks that the key isn't malformed before saving it
+    Hypothesis::new(&config.username, &config.developer_key)?;
+    let path = config.save()?;
+    println!("Saved credentials to {}", path.display());
+    Ok(())
+}
+
+/// Makes a client from `$HYPOTHESIS_NAME` and `$HYPOTHESIS_KEY`,
+/// falling back to the credentials saved by `hypothesis login` for any that aren't set
+pub fn client_from_env_or_config() -> color_eyre::Result<Hypothesis> {
+    let config = match (env::var("HYPOTHESIS_NAME"), env::var("HYPOTHESIS_KEY")) {
+        (Ok(_), Ok(_)) => None,
+        _ => Config::load()?,
+    };
+    let client = match config {
+        Some(config) => Hypothesis::with_url(
+            &env::var("HYPOTHESIS_NAME").unwrap_or(config.username),
+            &env::var("HYPOTHESIS_KEY").unwrap_or(config.developer_key),
+            &env::var("HYPOTHESIS_URL").unwrap_or_else(|_| API_URL.into()),
+        )?,
+        None => Hypothesis::from_env()?,
+    };
+    Ok(client)
+}
+
+/// Output format of annotations, groups and group members
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// one JSON object per line
+    Ndjson,
+    /// indented JSON, lists are written as a JSON array
+    Pretty,
+    /// comma-separated values of the main fields, with a header row
+    Csv,
+}
+
+/// Writes annotations, groups or members in an `OutputFormat`
+///
+/// With `pretty`, a list of rows is written as one JSON array so that the output is valid JSON.
+struct RowWriter<W: Write> {
+    writer: W,
+    format: OutputFormat,
+    array: bool,
+    rows: usize,
+}
+
+impl<W: Write> RowWriter<W> {
+    /// Writer for a list of rows
+    fn many<T: CsvRecord>(writer: W, format: OutputFormat) -> io::Result<Self> {
+        Self::new::<T>(writer, format, true)
+    }
+
+    /// Writer for a single row
+    fn one<T: CsvRecord>(writer: W, format: OutputFormat) -> io::Result<Self> {
+        Self::new::<T>(writer, format, false)
+    }
+
+    fn new<T: CsvRecord>(mut writer: W, format: OutputFormat, array: bool) -> io::Result<Self> {
+        match format {
+            OutputFormat::Csv => writeln!(writer, "{}", T::HEADER.join(","))?,
+            OutputFormat::Pretty if array => write!(writer, "[")?,
+            _ => (),
+        }
+        Ok(Self {
+            writer,
+            format,
+            array,
+            rows: 0,
+        })
+    }
+
+    fn write<T: Serialize + CsvRecord>(&mut self, item: &T) -> color_eyre::Result<()> {
+        match self.format {
+            OutputFormat::Ndjson => writeln!(self.writer, "{}", serde_json::to_string(item)?)?,
+            OutputFormat::Pretty if self.array => {
+                let separator = if self.rows == 0 { "\n" } else { ",\n" };
+                write!(
+                    self.writer,
+                    "{}{}",
+                    separator,
+                    serde_json::to_string_pretty(item)?
+                )?
+            }
+            OutputFormat::Pretty => {
+                writeln!(self.writer, "{}", serde_json::to_string_pretty(item)?)?
+            }
+            OutputFormat::Csv => writeln!(
+                self.writer,
+                "{}",
+                item.csv_record()
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )?,
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Closes the JSON array, if any, and flushes the writer
+    fn finish(mut self) -> io::Result<()> {
+        if self.format == OutputFormat::Pretty && self.array {
+            writeln!(self.writer, "\n]")?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Quotes a CSV field if it contains commas, quotes or newlines
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Objects that can be written as a row of CSV
+pub trait CsvRecord {
+    /// Column names
+    const HEADER: &'static [&'static str];
+    /// Column values, in the same order as `HEADER`
+    fn csv_record(&self) -> Vec<String>;
+}
+
+impl CsvRecord for Annotation {
+    const HEADER: &'static [&'static str] = &["id", "user", "uri", "text", "tags", "created"];
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.user.0.clone(),
+            self.uri.clone(),
+            self.text.clone(),
+            self.tags.join(";"),
+            self.created.to_rfc3339(),
+        ]
+    }
+}
+
+impl CsvRecord for Group {
+    const HEADER: &'static [&'static str] = &["id", "groupid", "name", "type"];
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.groupid.clone().unwrap_or_default(),
+            self.name.clone(),
+            match self.group_type {
+                Type::Private => "private",
+                Type::Open => "open",
+                Type::Restricted => "restricted",
+            }
+            .to_owned(),
+        ]
+    }
+}
+
+impl CsvRecord for Member {
+    const HEADER: &'static [&'static str] = &["userid", "username", "display_name", "authority"];
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.userid.clone(),
+            self.username.clone(),
+            self.display_name.clone().unwrap_or_default(),
+            self.authority.clone(),
+        ]
+    }
+}
+
 impl FromStr for Sort {
     type Err = CLIError;
 
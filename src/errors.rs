@@ -1,21 +1,30 @@
 //! API and CLI specific errors
 use std::fmt;
+use std::time::Duration;
 
 use reqwest::header::InvalidHeaderValue;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::annotations::Annotation;
+
 #[derive(Error, Debug)]
 pub enum HypothesisError {
-    #[error("Make sure input fields are valid")]
+    #[error("Make sure input fields are valid{}", api_error_details(.status, .raw_text))]
     APIError {
         #[source]
         source: APIError,
         serde_error: Option<serde_json::Error>,
         raw_text: String,
+        /// HTTP status code of the response, if the request reached the API
+        status: Option<StatusCode>,
     },
     #[error("Invalid header value")]
     HeaderError(#[from] InvalidHeaderValue),
+    /// The developer key is empty or contains whitespace or non-ASCII characters
+    #[error("The API key appears malformed, try copying it again from https://hypothes.is/account/developer")]
+    InvalidKey,
     #[error("Reqwest error")]
     ReqwestError(#[from] reqwest::Error),
     #[error("{suggestion:?}")]
@@ -30,6 +39,78 @@ pub enum HypothesisError {
     URLError(#[from] url::ParseError),
     #[error("Builder error: {0}")]
     BuilderError(String),
+    /// Too many requests were sent, wait for `retry_after` before trying again
+    #[error("Rate limited, retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+    /// The search query has no filters, see [`SearchQuery::allow_unbounded`](crate::annotations::SearchQuery::allow_unbounded)
+    #[error("The search query has no filters and would return every public annotation, set `allow_unbounded` to search anyway")]
+    UnboundedSearch,
+    /// An annotation in the `references` of a new annotation doesn't exist
+    #[error("Referenced annotation {0} doesn't exist")]
+    MissingReference(String),
+    /// `move_annotation` created the copy in the new group (`moved`) but couldn't delete the original
+    #[error("Copied the annotation to {} but couldn't delete the original", moved.id)]
+    MoveIncomplete {
+        moved: Box<Annotation>,
+        #[source]
+        source: Option<Box<HypothesisError>>,
+    },
+    /// Couldn't read the .env file
+    #[cfg(feature = "dotenv")]
+    #[error("Couldn't load .env file")]
+    DotenvError(#[from] dotenvy::Error),
+    /// Couldn't start the runtime of the blocking client
+    #[cfg(feature = "blocking")]
+    #[error("Couldn't start the async runtime")]
+    RuntimeError(#[source] std::io::Error),
+    /// The request didn't complete within the client's timeout
+    #[error("Request timed out")]
+    Timeout(#[source] reqwest::Error),
+}
+
+/// Maximum number of characters of the response body shown in `APIError` messages
+const MAX_DISPLAYED_BODY: usize = 500;
+
+/// Formats the status and (truncated) response body of an `APIError`
+fn api_error_details(status: &Option<StatusCode>, raw_text: &str) -> String {
+    let mut details = String::new();
+    if let Some(status) = status {
+        details.push_str(&format!(" ({})", status));
+    }
+    let raw_text = raw_text.trim();
+    if !raw_text.is_empty() {
+        details.push_str(": ");
+        details.extend(raw_text.chars().take(MAX_DISPLAYED_BODY));
+        if raw_text.chars().nth(MAX_DISPLAYED_BODY).is_some() {
+            details.push_str("...");
+        }
+    }
+    details
+}
+
+impl HypothesisError {
+    /// HTTP status code returned by the API, if any
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::APIError { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// Whether the API responded with 404 Not Found
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    /// Whether the API responded with 403 Forbidden
+    pub fn is_forbidden(&self) -> bool {
+        self.status() == Some(StatusCode::FORBIDDEN)
+    }
+
+    /// Whether the API responded with 401 Unauthorized
+    pub fn is_unauthorized(&self) -> bool {
+        self.status() == Some(StatusCode::UNAUTHORIZED)
+    }
 }
 
 /// Errors returned from the Hypothesis API
@@ -39,12 +120,19 @@ pub struct APIError {
     pub status: String,
     /// Cause of failure
     pub reason: String,
+    /// Further details some endpoints return (as `errors`), e.g. which fields failed validation
+    #[serde(default, alias = "errors", skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 impl fmt::Display for APIError {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Status: {}\nReason: {}", self.status, self.reason)
+        write!(f, "Status: {}\nReason: {}", self.status, self.reason)?;
+        if let Some(details) = &self.details {
+            write!(f, "\nDetails: {}", details)?;
+        }
+        Ok(())
     }
 }
 
@@ -58,4 +146,51 @@ pub enum CLIError {
     /// Failed to parse a command line argument into its corresponding type
     #[error("ParseError: {name:?} must be one of {types:?}")]
     ParseError { name: String, types: Vec<String> },
+    /// Some annotations couldn't be created by `annotations import`
+    #[error("Failed to import {failed} of {total} annotations")]
+    ImportError { failed: usize, total: usize },
+    /// Couldn't find the config directory to save credentials to
+    #[error("Couldn't find the config directory, set $XDG_CONFIG_HOME or $HOME")]
+    ConfigError,
+    /// A request failed, with the reason given by the API (or its response body)
+    #[error("Request failed: {reason}")]
+    RequestError { status: Option<u16>, reason: String },
+    /// The annotation or group doesn't exist, or the user can't access it
+    #[error("{kind} {id} not found")]
+    NotFound { kind: String, id: String },
+}
+
+#[cfg(feature = "cli")]
+impl CLIError {
+    /// Describes a failed request for the `kind` (e.g. "Annotation") with ID `id`,
+    /// as `NotFound` for 404 responses and a `RequestError` otherwise
+    pub fn from_request(error: &HypothesisError, kind: &str, id: &str) -> Self {
+        if error.is_not_found() {
+            Self::NotFound {
+                kind: kind.into(),
+                id: id.into(),
+            }
+        } else {
+            error.into()
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl From<&HypothesisError> for CLIError {
+    fn from(error: &HypothesisError) -> Self {
+        let reason = match error {
+            HypothesisError::APIError { source, .. } if !source.reason.is_empty() => {
+                source.reason.clone()
+            }
+            HypothesisError::APIError { raw_text, .. } if !raw_text.trim().is_empty() => {
+                raw_text.trim().chars().take(MAX_DISPLAYED_BODY).collect()
+            }
+            error => error.to_string(),
+        };
+        Self::RequestError {
+            status: error.status().map(|status| status.as_u16()),
+            reason,
+        }
+    }
 }
@@ -6,7 +6,18 @@ use clap::Parser;
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-use crate::is_default;
+use crate::{errors, is_default, Hypothesis};
+
+/// ID of the "Public" group, which annotations without a group are posted to
+pub const PUBLIC_GROUP_ID: &str = "__world__";
+
+/// Whether `id` could be a group ID, i.e. is made of letters, digits, `_` and `-`
+pub(crate) fn is_valid_group_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
 
 /// Which field to expand
 #[cfg_attr(feature = "cli", derive(ValueEnum))]
@@ -41,6 +52,129 @@ pub struct GroupFilters {
     pub expand: Vec<Expand>,
 }
 
+/// Options for creating a group, see [`Hypothesis::create_group_full`](crate::Hypothesis::create_group_full)
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use hypothesis::groups::{CreateGroupOptions, Type};
+/// let options = CreateGroupOptions::builder()
+///     .name("my group")
+///     .description("a group for example.com")
+///     .group_type(Type::Restricted)
+///     .uri_patterns(vec!["https://example.com/*".to_string()])
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Debug, Clone, Builder, PartialEq)]
+#[builder(build_fn(name = "builder"))]
+pub struct CreateGroupOptions {
+    /// Group name
+    #[builder(setter(into))]
+    pub name: String,
+    /// Group description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option), default)]
+    pub description: Option<String>,
+    /// Unique ID of the group within its authority, for groups of third-party authorities
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(into, strip_option), default)]
+    pub groupid: Option<String>,
+    /// Who can view and edit the group, private by default
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option), default)]
+    pub group_type: Option<Type>,
+    /// Restrict annotations in the group to these URIs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option), default)]
+    pub scopes: Option<Scope>,
+}
+
+impl CreateGroupOptions {
+    pub fn builder() -> CreateGroupOptionsBuilder {
+        CreateGroupOptionsBuilder::default()
+    }
+}
+
+impl CreateGroupOptionsBuilder {
+    /// Only allow annotating URIs matching these patterns (with `*` as a wildcard)
+    pub fn uri_patterns(&mut self, uri_patterns: Vec<String>) -> &mut Self {
+        self.scopes = Some(Some(Scope {
+            enforced: true,
+            uri_patterns,
+        }));
+        self
+    }
+
+    /// Builds a new `CreateGroupOptions`.
+    pub fn build(&self) -> Result<CreateGroupOptions, errors::HypothesisError> {
+        self.builder()
+            .map_err(|e| errors::HypothesisError::BuilderError(e.to_string()))
+    }
+}
+
+/// Role of a member in a group
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Created the group
+    Owner,
+    /// Can manage the group and its members
+    Admin,
+    /// Can hide and show annotations
+    Moderator,
+    /// Can read and annotate
+    Member,
+    /// A role added to the API after this version of the crate, don't use it as a filter
+    #[serde(other)]
+    Unknown,
+}
+
+impl Role {
+    /// Whether this role can hide and show annotations in the group
+    pub fn can_moderate(&self) -> bool {
+        matches!(self, Self::Owner | Self::Admin | Self::Moderator)
+    }
+}
+
+/// How `Hypothesis::find_groups_by_name` compares group names, ignoring case
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameMatch {
+    /// The whole name matches
+    Exact,
+    /// The name contains the search term
+    Contains,
+}
+
+impl NameMatch {
+    /// Whether `group_name` matches `name`
+    pub fn matches(&self, group_name: &str, name: &str) -> bool {
+        let group_name = group_name.to_lowercase();
+        let name = name.to_lowercase();
+        match self {
+            Self::Exact => group_name == name,
+            Self::Contains => group_name.contains(&name),
+        }
+    }
+}
+
+/// Page through and filter the members of a group
+///
+/// Fields left as `None` aren't sent, i.e. all members are returned in one response.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct MemberFilters {
+    /// Number of members per page
+    #[serde(rename = "page[size]", skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+    /// Page to return, starting at 1
+    #[serde(rename = "page[number]", skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<usize>,
+    /// Only return members with this role
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+}
+
 /// URL to the group's main (activity) page
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Links {
@@ -119,6 +253,66 @@ pub struct Group {
     pub group_type: Type,
 }
 
+impl Group {
+    /// Name of the group's organization, `None` if the organization wasn't expanded
+    /// (see [`Expand::Organization`]) or the user isn't authorized to access it
+    pub fn organization_name(&self) -> Option<&str> {
+        match &self.organization {
+            Organization::Organization(Some(org)) => Some(&org.name),
+            _ => None,
+        }
+    }
+
+    /// The group's organization, re-fetching the group with [`Expand::Organization`] if it
+    /// wasn't expanded (the API has no endpoint to fetch an organization by ID).
+    /// `None` if the user isn't authorized to access the organization.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let group = api.fetch_group(&group_id, Vec::new()).await?;
+    /// if let Some(org) = group.resolve_organization(&api).await? {
+    ///     println!("{} belongs to {}", group.name, org.name);
+    /// }
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn resolve_organization(
+        &self,
+        api: &Hypothesis,
+    ) -> Result<Option<Org>, errors::HypothesisError> {
+        if let Organization::Organization(org) = &self.organization {
+            return Ok(org.clone());
+        }
+        let group = api
+            .fetch_group(&self.id, vec![Expand::Organization])
+            .await?;
+        match group.organization {
+            Organization::Organization(org) => Ok(org),
+            Organization::String(_) => Ok(None),
+        }
+    }
+
+    /// Whether the user can annotate in this group, judging by its type alone.
+    ///
+    /// Anyone can annotate in open groups and the API only returns private groups to their
+    /// members. Restricted groups only let members annotate, which the group doesn't say,
+    /// so this returns `false` for them - check the user's [`Member::roles`] instead.
+    pub fn can_annotate(&self) -> bool {
+        match self.group_type {
+            Type::Open | Type::Private => true,
+            Type::Restricted => false,
+        }
+    }
+}
+
 /// Information about another user
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Member {
@@ -131,4 +325,14 @@ pub struct Member {
     /// string <= 30 characters
     #[serde(default)]
     pub display_name: Option<String>,
+    /// Roles of the user in the group, empty if the API didn't return them
+    #[serde(default)]
+    pub roles: Vec<Role>,
+}
+
+impl Member {
+    /// Whether the member can hide and show annotations in the group
+    pub fn can_moderate(&self) -> bool {
+        self.roles.iter().any(Role::can_moderate)
+    }
 }
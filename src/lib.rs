@@ -10,19 +10,23 @@
 //! A lightweight wrapper and CLI for the [Hypothesis Web API v1.0.0](https://h.readthedocs.io/en/latest/api-reference/v1/).
 //! It includes all APIKey authorized endpoints related to
 //! * annotations (create / update / delete / search / fetch / flag),
-//! * groups (create / update / list / fetch / leave / members)
+//! * groups (create / update / list / fetch / leave / delete / members / add or remove member)
 //! * profile (user information / groups)
 //!
 //! ## Installation and Usage
 //! ### Authorization
 //! You'll need a [Hypothesis](https://hypothes.is) account, and a personal API token obtained as described [here](https://h.readthedocs.io/en/latest/api/authorization/).
 //! Set the environment variables `$HYPOTHESIS_NAME` and `$HYPOTHESIS_KEY` to your username and the developer API key respectively.
+//! To use a self-hosted Hypothesis instance, set `$HYPOTHESIS_URL` to its API URL (defaults to `https://api.hypothes.is/api`).
+//! With the `dotenv` feature, `Hypothesis::from_dotenv` loads these from a `.env` file.
+//! Apps using the OAuth flow can use `Hypothesis::with_oauth_token` instead of a developer key.
 //!
 //! ### As a command-line utility:
 //! ```bash
 //! cargo install hypothesis
 //! ```
 //! Run `hypothesis --help` to see subcommands and options.
+//! Run `hypothesis login` to save your username and developer key instead of setting the environment variables.
 //! NOTE: the CLI doesn't currently have all the capabilities of the Rust crate, specifically bulk actions and updating dates are not supported.
 //!
 //! Generate shell completions:
@@ -35,11 +39,17 @@
 //! Add to your Cargo.toml:
 //! ```toml
 //! [dependencies]
-//! hypothesis = {version = "0.4.0", default-features = false}
+//! hypothesis = {version = "0.4.0", default-features = false, features = ["rustls-tls"]}
 //! # For a tokio runtime:
 //! tokio = { version = "0.2", features = ["macros"] }
 //! ```
 //!
+//! With `default-features = false` one of the TLS features must be enabled, the crate doesn't compile without one.
+//! Use the `native-tls` feature instead of `rustls-tls` to use the system's TLS library (e.g. OpenSSL).
+//! Without an async runtime, the `blocking` feature adds `hypothesis::blocking::Hypothesis` with the same main methods.
+//! The `tracing` feature logs each request (method, path and status) at debug level and response bodies at trace level.
+//! To work in one group, `Hypothesis::with_group` returns a client that puts created annotations in and restricts searches to that group.
+//!
 //! #### Examples
 //! ```rust no_run
 //! use hypothesis::Hypothesis;
@@ -65,6 +75,7 @@
 //! ```
 //! See the documentation of the API struct ([`Hypothesis`](https://docs.rs/crate/hypothesis/struct.Hypothesis.html)) for a list of possible queries.
 //! Use bulk functions to perform multiple actions - e.g. `api.fetch_annotations` instead of a loop around `api.fetch_annotation`.
+//! Code written against the `api::HypothesisApi` trait can be tested with a mock instead of the live API.
 //!
 //! Check the [documentation](https://docs.rs/crate/hypothesis) for more usage examples.
 //!
@@ -75,50 +86,207 @@
 //! Make sure you have a .env file (added to .gitignore) in the repo root with HYPOTHESIS_NAME, HYPOTHESIS_KEY, and TEST_GROUP_ID
 //!
 //! ### Caveats / Todo:
-//! - Only supports APIKey authorization and hypothes.is authority (i.e. single users).
-//! - `Target.selector.RangeSelector` doesn't seem to follow [W3C standards](https://www.w3.org/TR/annotation-model/#range-selector). It's just a hashmap for now.
-//! - `Annotation` hypermedia links are stored as a hashmap, b/c I don't know all the possible values.
+//! - Only supports APIKey and OAuth access token authorization (i.e. single users).
+//! - `Target.selector.RangeSelector` follows the Hypothesis client (XPaths and offsets) instead of [W3C standards](https://www.w3.org/TR/annotation-model/#range-selector).
 //! - Need to figure out how `Document` works to properly document it (hah).
-//! - Can't delete a group after making it, can leave it though (maybe it's the same thing?)
-//! - No idea what `UserProfile.preferences` and `UserProfile.features` mean.
 //! - CLI just dumps output as JSON, this is fine right? Fancier CLIs can build on top of this (or use the crate directly)
 #[macro_use]
 extern crate derive_builder;
 
 use std::collections::HashMap;
+#[cfg(feature = "dotenv")]
+use std::path::Path;
 use std::str::FromStr;
 use std::string::ParseError;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{env, fmt};
 
-use futures::future::try_join_all;
-use reqwest::{header, Url};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use reqwest::{header, Method, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 
-use crate::annotations::{Annotation, InputAnnotation, SearchQuery};
+use crate::annotations::{
+    Annotation, InputAnnotation, Moderation, ModerationAction, ModerationStatus, SearchPage,
+    SearchQuery, SearchResult,
+};
 use crate::errors::HypothesisError;
-use crate::groups::{Expand, Group, GroupFilters, Member};
+use crate::groups::{
+    CreateGroupOptions, Expand, Group, GroupFilters, Member, MemberFilters, NameMatch,
+};
 use crate::profile::UserProfile;
 
+#[cfg(not(any(feature = "rustls-tls", feature = "native-tls")))]
+compile_error!("hypothesis needs a TLS backend, enable the `rustls-tls` or `native-tls` feature");
+
 pub mod annotations;
+pub mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod bulk;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod errors;
 pub mod groups;
 pub mod profile;
+mod scoped;
+pub mod text;
+
+pub use scoped::ScopedHypothesis;
 
 /// Hypothesis API URL
 pub const API_URL: &str = "https://api.hypothes.is/api";
 
+/// Authority of hypothes.is user accounts
+pub const DEFAULT_AUTHORITY: &str = "hypothes.is";
+
+/// Default number of times a request is retried on connection errors and 5xx responses
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Default delay before the first retry
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Default timeout for a single request
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of requests a bulk operation keeps in flight at once
+pub const DEFAULT_MAX_CONCURRENCY: usize = 10;
+
+/// Number of members requested per page by `get_group_members_stream`
+const MEMBERS_PAGE_SIZE: usize = 100;
+
+/// Version of the Hypothesis API to request, sent in the `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApiVersion {
+    /// `application/vnd.hypothesis.v1+json`, what this crate's types are written for
+    #[default]
+    V1,
+    /// `application/vnd.hypothesis.v2+json`, responses may not match this crate's types
+    V2,
+}
+
+impl ApiVersion {
+    /// Media type of the version, for the `Accept` header
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::V1 => "application/vnd.hypothesis.v1+json",
+            Self::V2 => "application/vnd.hypothesis.v2+json",
+        }
+    }
+}
+
+/// Rate limit budget reported by the API in `X-RateLimit-*` response headers,
+/// see [`Hypothesis::last_rate_limit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateLimit {
+    /// Maximum number of requests in the current window (`X-RateLimit-Limit`)
+    pub limit: Option<u64>,
+    /// Requests left in the current window (`X-RateLimit-Remaining`)
+    pub remaining: Option<u64>,
+    /// When the window resets, as sent by the API in `X-RateLimit-Reset` (usually seconds)
+    pub reset: Option<u64>,
+}
+
+impl RateLimit {
+    /// Parses the `X-RateLimit-*` headers, `None` if the response has none of them
+    pub fn from_headers(headers: &header::HeaderMap) -> Option<Self> {
+        let parse = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+        let rate_limit = Self {
+            limit: parse("x-ratelimit-limit"),
+            remaining: parse("x-ratelimit-remaining"),
+            reset: parse("x-ratelimit-reset"),
+        };
+        (rate_limit != Self::default()).then_some(rate_limit)
+    }
+}
+
 /// checks if a variable is the default value of its type
 fn is_default<T: Default + PartialEq>(t: &T) -> bool {
     t == &T::default()
 }
 
+/// converts a serializable query into URL query parameters, repeating the key for each item in arrays
+fn query_params<T: Serialize>(query: &T) -> Result<Vec<(String, String)>, HypothesisError> {
+    let query: HashMap<String, serde_json::Value> =
+        serde_json::from_str(&serde_json::to_string(query).map_err(HypothesisError::SerdeError)?)
+            .map_err(HypothesisError::SerdeError)?;
+    Ok(query
+        .into_iter()
+        .flat_map(|(k, v)| {
+            if v.is_array() {
+                v.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|v| (k.clone(), v.to_string().replace('"', "")))
+                    .collect::<Vec<_>>()
+            } else {
+                vec![(k, v.to_string().replace('"', ""))]
+            }
+        })
+        .collect())
+}
+
+/// parses the number of seconds in a `Retry-After` header
+fn parse_retry_after(headers: &header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// maps reqwest timeouts to `HypothesisError::Timeout`
+fn reqwest_error(error: reqwest::Error) -> HypothesisError {
+    if error.is_timeout() {
+        HypothesisError::Timeout(error)
+    } else {
+        HypothesisError::ReqwestError(error)
+    }
+}
+
+/// Builds a reqwest client sending the API key or OAuth access token with every request
+fn authorized_client(
+    token: &str,
+    timeout: Option<Duration>,
+    api_version: ApiVersion,
+) -> Result<reqwest::Client, HypothesisError> {
+    // keys copied from the developer page often come with a trailing newline
+    let token = token.trim();
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(HypothesisError::InvalidKey);
+    }
+    let mut headers = header::HeaderMap::new();
+    headers.insert(
+        header::AUTHORIZATION,
+        header::HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(HypothesisError::HeaderError)?,
+    );
+    headers.insert(
+        header::ACCEPT,
+        header::HeaderValue::from_str(api_version.media_type())
+            .map_err(HypothesisError::HeaderError)?,
+    );
+    let mut client = reqwest::Client::builder().default_headers(headers);
+    #[cfg(feature = "native-tls")]
+    {
+        client = client.use_native_tls();
+    }
+    if let Some(timeout) = timeout {
+        client = client.timeout(timeout);
+    }
+    client.build().map_err(HypothesisError::ReqwestError)
+}
+
 pub fn serde_parse<'a, T: Deserialize<'a>>(text: &'a str) -> Result<T, errors::HypothesisError> {
     serde_json::from_str::<T>(text).map_err(|e| errors::HypothesisError::APIError {
         source: serde_json::from_str::<errors::APIError>(text).unwrap_or_default(),
         serde_error: Some(e),
         raw_text: text.to_owned(),
+        status: None,
     })
 }
 
@@ -126,10 +294,30 @@ pub fn serde_parse<'a, T: Deserialize<'a>>(text: &'a str) -> Result<T, errors::H
 pub struct Hypothesis {
     /// Authenticated user
     pub username: String,
-    /// "acct:{username}@hypothes.is"
+    /// "acct:{username}@{authority}"
     pub user: UserAccountID,
     /// authorized reqwest async client
     client: reqwest::Client,
+    /// API URL used for all requests, defaults to [`API_URL`]
+    base_url: String,
+    /// Authority the user's account belongs to, defaults to [`DEFAULT_AUTHORITY`]
+    authority: String,
+    /// Number of times a failed request is retried
+    max_retries: u32,
+    /// Delay before the first retry, doubled after each attempt
+    retry_delay: Duration,
+    /// Whether to also retry POST requests (which could create duplicates)
+    retry_non_idempotent: bool,
+    /// Maximum number of concurrent requests made by bulk operations
+    max_concurrency: usize,
+    /// Request timeout, kept to rebuild the client when the token changes
+    timeout: Option<Duration>,
+    /// API version requested in the `Accept` header
+    api_version: ApiVersion,
+    /// ETag and body of previous GET responses by URL, if caching is enabled
+    etag_cache: Option<Arc<Mutex<HashMap<String, (String, String)>>>>,
+    /// Rate limit reported in the headers of the most recent response that had them
+    rate_limit: Mutex<Option<RateLimit>>,
 }
 
 impl Hypothesis {
@@ -139,48 +327,112 @@ impl Hypothesis {
     /// ```
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// #     Ok(())
     /// # }
     /// ```
     pub fn new(username: &str, developer_key: &str) -> Result<Self, HypothesisError> {
-        let user = UserAccountID::from_str(username).expect("This should never error");
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::AUTHORIZATION,
-            header::HeaderValue::from_str(&format!("Bearer {}", developer_key))
-                .map_err(HypothesisError::HeaderError)?,
-        );
-        headers.insert(
-            header::ACCEPT,
-            header::HeaderValue::from_str("application/vnd.hypothesis.v1+json")
-                .map_err(HypothesisError::HeaderError)?,
-        );
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
+        Self::with_url(username, developer_key, API_URL)
+    }
+
+    /// Make a new Hypothesis client pointing to a different API URL,
+    /// e.g. for a self-hosted Hypothesis instance
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// let api = Hypothesis::with_url("my_username", "my_developer_key", "https://hypothesis.example.com/api")?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_url(
+        username: &str,
+        developer_key: &str,
+        base_url: &str,
+    ) -> Result<Self, HypothesisError> {
+        Self::builder()
+            .username(username)
+            .developer_key(developer_key)
+            .base_url(base_url)
             .build()
-            .map_err(HypothesisError::ReqwestError)?;
-        Ok(Self {
-            username: username.into(),
-            user,
-            client,
-        })
+    }
+
+    /// Make a new Hypothesis client with an OAuth access token instead of a developer key
+    ///
+    /// Access tokens are short-lived, use `set_token` to replace an expired token with a refreshed one.
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// let mut api = Hypothesis::with_oauth_token("my_username", "my_access_token")?;
+    /// api.set_token("my_refreshed_access_token")?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_oauth_token(username: &str, access_token: &str) -> Result<Self, HypothesisError> {
+        Self::builder()
+            .username(username)
+            .access_token(access_token)
+            .build()
+    }
+
+    /// Replace the developer key or OAuth access token used for all further requests
+    pub fn set_token(&mut self, token: &str) -> Result<(), HypothesisError> {
+        self.client = authorized_client(token, self.timeout, self.api_version)?;
+        Ok(())
+    }
+
+    /// Authority the user's account belongs to, e.g. "hypothes.is"
+    pub fn authority(&self) -> &str {
+        &self.authority
+    }
+
+    /// The authorized reqwest client, to call endpoints this crate doesn't wrap yet
+    ///
+    /// Requests made with it include the authorization header and timeout, but aren't retried.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let response = api.client().get(format!("{}/profile", api.base_url())).send().await?;
+    /// assert!(response.status().is_success());
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// API URL used for all requests, e.g. "https://api.hypothes.is/api"
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Configure a new Hypothesis client, see [`HypothesisBuilder`]
+    pub fn builder() -> HypothesisBuilder {
+        HypothesisBuilder::default()
     }
 
     /// Make a new Hypothesis client from environment variables.
     /// Username from `$HYPOTHESIS_NAME`,
     /// Developer key from `$HYPOTHESIS_KEY`
-    /// (see [here](https://h.readthedocs.io/en/latest/api/authorization/) on how to get one)
+    /// (see [here](https://h.readthedocs.io/en/latest/api/authorization/) on how to get one),
+    /// API URL from `$HYPOTHESIS_URL` if set, otherwise [`API_URL`]
     /// # Example
     /// ```
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// #    use std::env;
-    /// #    dotenv::dotenv()?;
-    /// #    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #    let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// #    env::set_var("HYPOTHESIS_NAME", username);
     /// #    env::set_var("HYPOTHESIS_KEY", developer_key);
     /// use hypothesis::Hypothesis;
@@ -200,7 +452,188 @@ impl Hypothesis {
                 suggestion: "Set the environment variable HYPOTHESIS_KEY to your personal API key"
                     .into(),
             })?;
-        Self::new(&username, &developer_key)
+        let base_url = env::var("HYPOTHESIS_URL").unwrap_or_else(|_| API_URL.into());
+        Self::with_url(&username, &developer_key, &base_url)
+    }
+
+    /// Make a new Hypothesis client from environment variables (see [`Hypothesis::from_env`]),
+    /// after loading them from a .env file at `path`.
+    ///
+    /// If `path` is `None`, looks for a .env file in the current directory and its parents,
+    /// falling back to the existing environment if there isn't one.
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// let api = Hypothesis::from_dotenv(None)?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "dotenv")]
+    pub fn from_dotenv(path: Option<&Path>) -> Result<Self, HypothesisError> {
+        match path {
+            Some(path) => dotenvy::from_path(path)?,
+            None => match dotenvy::dotenv() {
+                Err(e) if e.not_found() => (),
+                result => {
+                    result?;
+                }
+            },
+        }
+        Self::from_env()
+    }
+
+    /// Rate limit budget from the `X-RateLimit-*` headers of the most recent response that had them,
+    /// `None` if the API hasn't sent any
+    pub fn last_rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Sends a request and returns the response text.
+    /// Responses with a non-success status code are returned as [`HypothesisError::APIError`].
+    ///
+    /// Connection errors and 5xx responses are retried with exponential backoff,
+    /// except for POST requests unless `retry_non_idempotent` is set.
+    /// Rate limited (429) requests are retried after the duration given in the `Retry-After` header,
+    /// or fail with [`HypothesisError::RateLimited`] once retries are exhausted.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String, HypothesisError> {
+        Ok(self.send_with_headers(request).await?.1)
+    }
+
+    /// Same as `send`, also returning the response headers
+    async fn send_with_headers(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<(header::HeaderMap, String), HypothesisError> {
+        let request = request.build().map_err(HypothesisError::ReqwestError)?;
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "request",
+            method = %request.method(),
+            path = request.url().path(),
+            status = tracing::field::Empty,
+        );
+        let response = self.execute(request);
+        #[cfg(feature = "tracing")]
+        let response = tracing::Instrument::instrument(response, span);
+        response.await
+    }
+
+    /// Executes a request, retrying as described in `send`
+    async fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<(header::HeaderMap, String), HypothesisError> {
+        let max_retries = if request.method() != Method::POST || self.retry_non_idempotent {
+            self.max_retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .client
+                .execute(
+                    request
+                        .try_clone()
+                        .expect("request bodies are never streamed"),
+                )
+                .await;
+            if let Some(rate_limit) = result
+                .as_ref()
+                .ok()
+                .and_then(|response| RateLimit::from_headers(response.headers()))
+            {
+                *self.rate_limit.lock().unwrap() = Some(rate_limit);
+            }
+            let backoff = self
+                .retry_delay
+                .saturating_mul(2u32.saturating_pow(attempt));
+            let delay = match &result {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = parse_retry_after(response.headers()).unwrap_or(backoff);
+                    // rate limited requests aren't processed, so even POSTs are safe to retry
+                    if attempt >= self.max_retries {
+                        return Err(HypothesisError::RateLimited { retry_after });
+                    }
+                    Some(retry_after)
+                }
+                Ok(response) if response.status().is_server_error() && attempt < max_retries => {
+                    Some(backoff)
+                }
+                Err(error)
+                    if attempt < max_retries
+                        && (error.is_connect() || error.is_timeout() || error.is_request()) =>
+                {
+                    Some(backoff)
+                }
+                _ => None,
+            };
+            match delay {
+                Some(delay) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(attempt, ?delay, "retrying request");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => {
+                    let response = result.map_err(reqwest_error)?;
+                    let status = response.status();
+                    let headers = response.headers().clone();
+                    let text = response.text().await.map_err(reqwest_error)?;
+                    #[cfg(feature = "tracing")]
+                    {
+                        tracing::Span::current().record("status", status.as_u16());
+                        tracing::debug!(%status, "response");
+                        tracing::trace!(body = %text, "response body");
+                    }
+                    return if status.is_success() {
+                        Ok((headers, text))
+                    } else {
+                        Err(HypothesisError::APIError {
+                            source: serde_json::from_str::<errors::APIError>(&text)
+                                .unwrap_or_default(),
+                            serde_error: None,
+                            raw_text: text,
+                            status: Some(status),
+                        })
+                    };
+                }
+            }
+        }
+    }
+
+    /// Sends a GET request, reusing the cached response if the API answers 304 Not Modified
+    /// to `If-None-Match` with the response's previous ETag. Only caches if `etag_cache` is enabled.
+    async fn get_cached(&self, url: &str) -> Result<String, HypothesisError> {
+        let cache = match &self.etag_cache {
+            Some(cache) => cache,
+            None => return self.send(self.client.get(url)).await,
+        };
+        let cached = cache.lock().unwrap().get(url).cloned();
+        let mut request = self.client.get(url);
+        if let Some((etag, _)) = &cached {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        match self.send_with_headers(request).await {
+            Ok((headers, text)) => {
+                if let Some(etag) = headers
+                    .get(header::ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                {
+                    cache
+                        .lock()
+                        .unwrap()
+                        .insert(url.to_string(), (etag.to_string(), text.clone()));
+                }
+                Ok(text)
+            }
+            Err(error) if error.status() == Some(StatusCode::NOT_MODIFIED) => match cached {
+                Some((_, text)) => Ok(text),
+                None => Err(error),
+            },
+            Err(error) => Err(error),
+        }
     }
 
     /// Create a new annotation
@@ -215,10 +648,10 @@ impl Hypothesis {
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
     /// use hypothesis::annotations::InputAnnotation;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #     let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
     ///
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let annotation = api.create_annotation(&InputAnnotation::builder()
@@ -236,64 +669,64 @@ impl Hypothesis {
         annotation: &InputAnnotation,
     ) -> Result<Annotation, HypothesisError> {
         let text = self
-            .client
-            .post(&format!("{}/annotations", API_URL))
-            .json(annotation)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+            .send(
+                self.client
+                    .post(&format!("{}/annotations", self.base_url))
+                    .json(annotation),
+            )
+            .await?;
         serde_parse::<Annotation>(&text)
     }
 
-    /// Create many new annotations
+    /// Create a new annotation after checking that the annotations in its `references` exist
     ///
-    /// Posts multiple new annotation objects asynchronously to Hypothesis.
-    /// Returns [`Annotation`](annotations/struct.Annotation.html)s as output.
-    /// See [`InputAnnotation`'s](annotations/struct.InputAnnotation.html) docs for examples on what
-    /// you can add to an annotation.
+    /// The API accepts replies to annotations that don't exist, leaving orphaned replies.
+    /// This checks every referenced ID first (one request each) and errors with
+    /// [`HypothesisError::MissingReference`] without creating the annotation if one is missing.
     ///
     /// # Example
     /// ```
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # use hypothesis::Hypothesis;
-    /// # use hypothesis::annotations::InputAnnotation;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #     let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::InputAnnotation;
+    /// use hypothesis::errors::HypothesisError;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
     /// let api = Hypothesis::new(&username, &developer_key)?;
-    /// let input_annotations = vec![
-    ///     InputAnnotation::builder()
-    ///         .text("first")
-    ///         .uri("http://example.com")
-    ///         .group(&group_id)
-    ///         .build()?,
-    ///     InputAnnotation::builder()
-    ///         .text("second")
-    ///         .uri("http://example.com")
-    ///         .group(&group_id)   
-    ///         .build()?
-    /// ];
-    /// let annotations = api.create_annotations(&input_annotations).await?;
-    /// assert_eq!(&annotations[0].text, "first");
-    /// assert_eq!(&annotations[1].text, "second");
-    /// #    api.delete_annotations(&annotations.into_iter().map(|a| a.id).collect::<Vec<_>>()).await?;
+    /// let reply = InputAnnotation::builder()
+    ///     .text("a reply")
+    ///     .uri("http://example.com")
+    ///     .group(&group_id)
+    ///     .references(vec!["no-such-annotation".to_string()])
+    ///     .build()?;
+    /// assert!(matches!(
+    ///     api.create_annotation_checked(&reply).await,
+    ///     Err(HypothesisError::MissingReference(_))
+    /// ));
     /// #    Ok(())
     /// # }
     /// ```
-    pub async fn create_annotations(
+    pub async fn create_annotation_checked(
         &self,
-        annotations: &[InputAnnotation],
-    ) -> Result<Vec<Annotation>, HypothesisError> {
-        let futures: Vec<_> = annotations
+        annotation: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        let exists: Vec<bool> = stream::iter(annotation.references.iter())
+            .map(|id| self.annotation_exists(id))
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await?;
+        if let Some((id, _)) = annotation
+            .references
             .iter()
-            .map(|a| self.create_annotation(a))
-            .collect();
-        async { try_join_all(futures).await }.await
+            .zip(exists)
+            .find(|(_, exists)| !exists)
+        {
+            return Err(HypothesisError::MissingReference(id.clone()));
+        }
+        self.create_annotation(annotation).await
     }
 
     /// Update an existing annotation
@@ -306,10 +739,10 @@ impl Hypothesis {
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
     /// use hypothesis::annotations::InputAnnotation;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #     let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let mut annotation = api.create_annotation(&InputAnnotation::builder()
     ///                   .text("string")
@@ -330,28 +763,120 @@ impl Hypothesis {
         annotation: &Annotation,
     ) -> Result<Annotation, HypothesisError> {
         let text = self
-            .client
-            .patch(&format!("{}/annotations/{}", API_URL, annotation.id))
-            .json(&annotation)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+            .send(
+                self.client
+                    .patch(&format!("{}/annotations/{}", self.base_url, annotation.id))
+                    .json(&annotation),
+            )
+            .await?;
         serde_parse::<Annotation>(&text)
     }
 
-    /// Update many annotations at once
-    pub async fn update_annotations(
+    /// Update only some fields of an existing annotation
+    ///
+    /// Sends just the fields of `changes` that aren't left as default, so the annotation
+    /// doesn't have to be fetched first. Returns the modified [`Annotation`](annotations/struct.Annotation.html)
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::InputAnnotation;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let annotation = api.create_annotation(&InputAnnotation::builder()
+    ///                   .text("string")
+    ///                   .uri("http://example.com")
+    ///                   .tags(vec!["tag1".to_string()])
+    ///                   .group(&group_id)
+    ///                   .build()?).await?;
+    /// let changes = InputAnnotation::builder().text("New String").build()?;
+    /// let updated_annotation = api.patch_annotation(&annotation.id, &changes).await?;
+    /// assert_eq!(&updated_annotation.text, "New String");
+    /// assert_eq!(updated_annotation.tags, vec!["tag1".to_string()]);
+    /// #    api.delete_annotation(&updated_annotation.id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn patch_annotation(
         &self,
-        annotations: &[Annotation],
-    ) -> Result<Vec<Annotation>, HypothesisError> {
-        let futures: Vec<_> = annotations
-            .iter()
-            .map(|a| self.update_annotation(a))
-            .collect();
-        async { try_join_all(futures).await }.await
+        id: &str,
+        changes: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        let text = self
+            .send(
+                self.client
+                    .patch(&format!("{}/annotations/{}", self.base_url, id))
+                    .json(changes),
+            )
+            .await?;
+        serde_parse::<Annotation>(&text)
+    }
+
+    /// Add tags to an annotation, keeping its existing tags in order and skipping duplicates
+    ///
+    /// Fetches the annotation and sends only the updated tags. Returns the modified annotation.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::InputAnnotation;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let annotation = api.create_annotation(&InputAnnotation::builder()
+    ///                   .text("string")
+    ///                   .uri("http://example.com")
+    ///                   .tags(vec!["tag1".to_string()])
+    ///                   .group(&group_id)
+    ///                   .build()?).await?;
+    /// let annotation = api.add_tags(&annotation.id, &["tag2".into(), "tag1".into(), "tag2".into()]).await?;
+    /// assert_eq!(annotation.tags, vec!["tag1".to_string(), "tag2".to_string()]);
+    /// let annotation = api.remove_tags(&annotation.id, &["tag1".into(), "tag2".into()]).await?;
+    /// assert!(annotation.tags.is_empty());
+    /// #    api.delete_annotation(&annotation.id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn add_tags(&self, id: &str, tags: &[String]) -> Result<Annotation, HypothesisError> {
+        let mut new_tags = self.fetch_annotation(id).await?.tags;
+        for tag in tags {
+            if !new_tags.contains(tag) {
+                new_tags.push(tag.clone());
+            }
+        }
+        self.patch_tags(id, new_tags).await
+    }
+
+    /// Remove tags from an annotation, keeping the order of its other tags
+    ///
+    /// Removing all tags leaves the annotation without tags. Returns the modified annotation.
+    /// See `add_tags` for an example.
+    pub async fn remove_tags(
+        &self,
+        id: &str,
+        tags: &[String],
+    ) -> Result<Annotation, HypothesisError> {
+        let mut new_tags = self.fetch_annotation(id).await?.tags;
+        new_tags.retain(|tag| !tags.contains(tag));
+        self.patch_tags(id, new_tags).await
+    }
+
+    /// Replaces an annotation's tags, an empty list is sent as is to clear them
+    async fn patch_tags(&self, id: &str, tags: Vec<String>) -> Result<Annotation, HypothesisError> {
+        let changes = InputAnnotation {
+            tags: Some(tags),
+            ..Default::default()
+        };
+        self.patch_annotation(id, &changes).await
     }
 
     /// Search for annotations with optional filters
@@ -366,9 +891,9 @@ impl Hypothesis {
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::{Hypothesis, UserAccountID};
     /// use hypothesis::annotations::SearchQuery;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// /// Search for your own annotations:
     /// let search_query = SearchQuery::builder().user(&api.user.0).build()?;
@@ -381,143 +906,756 @@ impl Hypothesis {
         &self,
         query: &SearchQuery,
     ) -> Result<Vec<Annotation>, HypothesisError> {
-        let query: HashMap<String, serde_json::Value> = serde_json::from_str(
-            &serde_json::to_string(&query).map_err(HypothesisError::SerdeError)?,
-        )
-        .map_err(HypothesisError::SerdeError)?;
-        let url = Url::parse_with_params(
-            &format!("{}/search", API_URL),
-            query
-                .into_iter()
-                .flat_map(|(k, v)| {
-                    if v.is_array() {
-                        v.as_array()
-                            .unwrap()
-                            .iter()
-                            .map(|v| (k.clone(), v.to_string().replace('"', "")))
-                            .collect::<Vec<_>>()
-                    } else {
-                        vec![(k, v.to_string().replace('"', ""))]
-                    }
-                })
-                .collect::<Vec<_>>(),
-        )
-        .map_err(HypothesisError::URLError)?;
-        let text = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        #[derive(Deserialize, Debug, Clone, PartialEq)]
-        struct SearchResult {
-            rows: Vec<Annotation>,
-            total: usize,
-        }
-        Ok(serde_parse::<SearchResult>(&text)?.rows)
+        Ok(self.search_annotations_with_total(query).await?.0)
     }
 
-    /// Retrieve all annotations matching query
-    /// See  [`SearchQuery`](annotations/struct.SearchQuery.html) for filtering options
-    pub async fn search_annotations_return_all(
+    /// Search for annotations whose quoted text contains `quote` as an exact substring
+    ///
+    /// The API's `quote` filter matches tokens, so it also returns annotations that only contain
+    /// some of the words of `quote`. This sets `quote` on the query and keeps only the annotations
+    /// with a `TextQuoteSelector` containing it (see [`Annotation::contains_quote`]),
+    /// so fewer than `limit` annotations may be returned.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let query = SearchQuery::builder().user(&api.user.0).build()?;
+    /// let annotations = api.search_annotations_by_quote("illustrative examples", &query).await?;
+    /// assert!(annotations.iter().all(|annotation| annotation.contains_quote("illustrative examples")));
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn search_annotations_by_quote(
         &self,
-        query: &mut SearchQuery,
+        quote: &str,
+        query: &SearchQuery,
     ) -> Result<Vec<Annotation>, HypothesisError> {
-        let mut annotations = Vec::new();
-        loop {
-            let next = self.search_annotations(query).await?;
-            if next.is_empty() {
-                break;
-            }
-            query.search_after = next[next.len() - 1].updated.to_rfc3339();
-            annotations.extend_from_slice(&next);
-        }
+        let mut query = query.clone();
+        query.quote = quote.to_string();
+        let mut annotations = self.search_annotations(&query).await?;
+        annotations.retain(|annotation| annotation.contains_quote(quote));
         Ok(annotations)
     }
 
-    /// Fetch annotation by ID
+    /// Search for the authenticated user's annotations on a page
     ///
+    /// Fills in `user` as "acct:{username}@{authority}" so it doesn't have to be built by hand.
+    /// Returns at most 200 annotations, use `search_annotations_return_all` with a
+    /// [`SearchQuery`](annotations/struct.SearchQuery.html) if you expect more.
     /// # Example
     /// ```
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #    use hypothesis::annotations::InputAnnotation;
-    /// #    dotenv::dotenv()?;
-    /// #    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #    let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #    let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// let api = Hypothesis::new(&username, &developer_key)?;
-    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
-    /// #                       .text("string")
-    /// #                       .uri("http://example.com")
-    /// #                       .group(group_id).build()?).await?;
-    /// #    let annotation_id = annotation.id.to_owned();    
-    /// let annotation = api.fetch_annotation(&annotation_id).await?;
-    /// assert_eq!(annotation.id, annotation_id);
-    /// #    api.delete_annotation(&annotation.id).await?;
-    /// #    Ok(())
+    /// let annotations = api.search_my_annotations("https://www.example.com").await?;
+    /// # assert!(annotations.iter().all(|annotation| annotation.user == api.user));
+    /// #     Ok(())
     /// # }
     /// ```
-    pub async fn fetch_annotation(&self, id: &str) -> Result<Annotation, HypothesisError> {
-        let text = self
-            .client
-            .get(&format!("{}/annotations/{}", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        serde_parse::<Annotation>(&text)
-    }
-
-    /// Fetch multiple annotations by ID
-    pub async fn fetch_annotations(
+    pub async fn search_my_annotations(
         &self,
-        ids: &[String],
+        uri: &str,
     ) -> Result<Vec<Annotation>, HypothesisError> {
-        let futures: Vec<_> = ids.iter().map(|id| self.fetch_annotation(id)).collect();
-        async { try_join_all(futures).await }.await
+        let query = SearchQuery::builder()
+            .user(&self.user.0)
+            .uri(uri)
+            .limit(200)
+            .build()?;
+        self.search_annotations(&query).await
     }
 
-    /// Delete annotation by ID
+    /// Search for annotations with optional filters, also returning the total number of matches
     ///
+    /// Useful for showing e.g. "showing 50 of 1240", use `count_annotations` if only the total is needed.
     /// # Example
     /// ```
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #    use hypothesis::annotations::InputAnnotation;
-    /// #    dotenv::dotenv()?;
-    /// #    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #    let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #    let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// let api = Hypothesis::new(&username, &developer_key)?;
-    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
-    /// #                       .text("string")
-    /// #                       .uri("http://example.com")
-    /// #                       .group(group_id).build()?).await?;
-    /// #    let annotation_id = annotation.id.to_owned();    
-    /// let deleted = api.delete_annotation(&annotation_id).await?;
-    /// assert!(deleted);
-    /// assert!(api.fetch_annotation(&annotation_id).await.is_err());
-    /// #    Ok(())
+    /// let search_query = SearchQuery::builder().user(&api.user.0).limit(1).build()?;
+    /// let (annotations, total) = api.search_annotations_with_total(&search_query).await?;
+    /// #     assert_eq!(annotations.len(), 1);
+    /// #     assert!(total >= annotations.len());
+    /// #     Ok(())
     /// # }
     /// ```
-    pub async fn delete_annotation(&self, id: &str) -> Result<bool, HypothesisError> {
-        let text = self
-            .client
-            .delete(&format!("{}/annotations/{}", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+    pub async fn search_annotations_with_total(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<(Vec<Annotation>, usize), HypothesisError> {
+        let (mut annotations, total) = self.search_page(query).await?;
+        annotations.retain(|annotation| query.matches_tags_any(annotation));
+        Ok((annotations, total))
+    }
+
+    /// Search for one page of annotations, with a cursor to fetch the next page
+    ///
+    /// Pass `None` as the cursor for the first page, then the previous page's `next_cursor`.
+    /// Unlike `search_annotations_return_all`, the query isn't modified so it can be reused,
+    /// and its `sort` and `order` are kept. The query's `offset` is ignored when a cursor is given.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let search_query = SearchQuery::builder().user(&api.user.0).limit(2).build()?;
+    /// let mut annotations = Vec::new();
+    /// let mut cursor = None;
+    /// loop {
+    ///     let page = api.search_annotations_page(&search_query, cursor.as_deref()).await?;
+    ///     annotations.extend(page.annotations);
+    ///     cursor = page.next_cursor;
+    ///     if cursor.is_none() || annotations.len() >= 6 {
+    ///         break;
+    ///     }
+    /// }
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn search_annotations_page(
+        &self,
+        query: &SearchQuery,
+        cursor: Option<&str>,
+    ) -> Result<SearchPage, HypothesisError> {
+        let mut query = query.clone();
+        if let Some(cursor) = cursor {
+            query.search_after = cursor.into();
+            query.offset = 0;
+        }
+        let (mut annotations, total) = self.search_page(&query).await?;
+        let next_cursor = match annotations.last() {
+            Some(last) if annotations.len() >= query.limit as usize => Some(query.cursor(last)),
+            _ => None,
+        };
+        annotations.retain(|annotation| query.matches_tags_any(annotation));
+        Ok(SearchPage {
+            annotations,
+            next_cursor,
+            total,
+        })
+    }
+
+    /// Search for annotations, returning the API's response as is
+    ///
+    /// Returns the matching annotations (`rows`) along with the `total` number of matches,
+    /// e.g. to build paginated views. Unlike `search_annotations`, `tags_any` isn't applied.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let search_query = SearchQuery::builder().user(&api.user.0).limit(10).build()?;
+    /// let result = api.search_annotations_raw(&search_query).await?;
+    /// println!("showing {} of {}", result.rows.len(), result.total);
+    /// #     assert!(result.rows.len() <= 10);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn search_annotations_raw(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<SearchResult, HypothesisError> {
+        let url =
+            Url::parse_with_params(&format!("{}/search", self.base_url), query_params(query)?)
+                .map_err(HypothesisError::URLError)?;
+        let text = self.send(self.client.get(url)).await?;
+        serde_parse::<SearchResult>(&text)
+    }
+
+    /// Fetches one page of search results along with the total, without client-side filtering
+    async fn search_page(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<(Vec<Annotation>, usize), HypothesisError> {
+        let result = self.search_annotations_raw(query).await?;
+        Ok((result.rows, result.total))
+    }
+
+    /// Count the annotations matching a search query, without fetching them
+    ///
+    /// `limit`, `offset`, `search_after`, `since` and `until` are ignored.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let search_query = SearchQuery::builder().user(&api.user.0).build()?;
+    /// let count = api.count_annotations(&search_query).await?;
+    /// #     assert!(count > 0);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn count_annotations(&self, query: &SearchQuery) -> Result<usize, HypothesisError> {
+        let query = SearchQuery {
+            limit: 0,
+            offset: 0,
+            search_after: String::new(),
+            ..query.clone()
+        };
+        let url =
+            Url::parse_with_params(&format!("{}/search", self.base_url), query_params(&query)?)
+                .map_err(HypothesisError::URLError)?;
+        let text = self.send(self.client.get(url)).await?;
+        #[derive(Deserialize, Debug, Clone, PartialEq)]
+        struct CountResult {
+            total: usize,
+        }
+        Ok(serde_parse::<CountResult>(&text)?.total)
+    }
+
+    /// Retrieve all annotations matching query
+    /// See  [`SearchQuery`](annotations/struct.SearchQuery.html) for filtering options
+    ///
+    /// Pages through the results with `search_after`, so the query's `sort` and `order` are
+    /// overridden to sort by ascending `updated` date.
+    /// If `since` or `until` are set on the query, only annotations updated within that range are returned.
+    ///
+    /// Errors with `HypothesisError::UnboundedSearch` if the query has no filters, since it would page
+    /// through every public annotation, unless `allow_unbounded` is set.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::{Order, SearchQuery};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// // the default descending order is overridden
+    /// let mut search_query = SearchQuery::builder().user(&api.user.0).limit(2).build()?;
+    /// assert_eq!(search_query.order, Order::Desc);
+    /// let annotations = api.search_annotations_return_all(&mut search_query).await?;
+    /// assert_eq!(search_query.order, Order::Asc);
+    /// assert!(annotations.windows(2).all(|pair| pair[0].updated <= pair[1].updated));
+    /// # let mut ids: Vec<_> = annotations.iter().map(|annotation| &annotation.id).collect();
+    /// # ids.sort();
+    /// # ids.dedup();
+    /// # assert_eq!(ids.len(), annotations.len());
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn search_annotations_return_all(
+        &self,
+        query: &mut SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        if query.is_unbounded() && !query.allow_unbounded {
+            return Err(HypothesisError::UnboundedSearch);
+        }
+        query.prepare_pagination();
+        let mut annotations = Vec::new();
+        loop {
+            let (mut next, _) = self.search_page(query).await?;
+            if next.is_empty() {
+                break;
+            }
+            query.search_after = next[next.len() - 1].updated.to_rfc3339();
+            let end = query.until_index(&next);
+            if let Some(end) = end {
+                next.truncate(end);
+            }
+            next.retain(|annotation| query.matches_tags_any(annotation));
+            annotations.extend(next);
+            if end.is_some() {
+                break;
+            }
+        }
+        Ok(annotations)
+    }
+
+    /// Retrieve every annotation made by a user, e.g. for a backup
+    ///
+    /// Includes annotations in all groups the authenticated user can read,
+    /// sorted by ascending `updated` date.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let annotations = api.fetch_all_user_annotations(&api.user).await?;
+    /// #     assert!(annotations.iter().all(|annotation| annotation.user == api.user));
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_all_user_annotations(
+        &self,
+        user: &UserAccountID,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let mut query = SearchQuery::builder().user(&user.0).limit(200).build()?;
+        self.search_annotations_return_all(&mut query).await
+    }
+
+    /// Retrieve all annotations created or updated after `since`, e.g. to keep a local copy in sync
+    ///
+    /// Searches in `group`. Annotations are sorted by ascending `updated` date,
+    /// so the last annotation's `updated` date can be used as `since` for the next sync.
+    /// Annotations updated exactly at `since` are not included.
+    /// A blank `group` would be an unbounded search of every public annotation and returns
+    /// [`HypothesisError::UnboundedSearch`], use `search_annotations_return_all` with
+    /// `allow_unbounded` to opt in to that.
+    ///
+    /// Deleted annotations can't be found this way, use `annotation_exists` to check whether
+    /// locally stored annotations were deleted.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use chrono::{Duration, Utc};
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let since = Utc::now() - Duration::days(1);
+    /// let annotations = api.sync_since(since, &group_id).await?;
+    /// assert!(annotations.iter().all(|annotation| annotation.updated > since));
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn sync_since(
+        &self,
+        since: DateTime<Utc>,
+        group: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let mut query = SearchQuery::builder()
+            .since(since)
+            .limit(200)
+            .group(vec![group.to_string()])
+            .build()?;
+        self.search_annotations_return_all(&mut query).await
+    }
+
+    /// Retrieve all annotations updated after the annotation `last_id`, e.g. the last one exported
+    ///
+    /// Fetches `last_id` to get its `updated` date and continues like `sync_since` from there,
+    /// in `group`. A blank `group` returns [`HypothesisError::UnboundedSearch`] like in
+    /// `sync_since`, without fetching `last_id`. If `last_id` was deleted this errors (`is_not_found`),
+    /// fall back to `sync_since` with a stored date in that case.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::InputAnnotation;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let first = api.create_annotation(&InputAnnotation::builder()
+    ///     .text("first")
+    ///     .uri("https://www.example.com")
+    ///     .group(&group_id)
+    ///     .build()?).await?;
+    /// let second = api.create_annotation(&InputAnnotation::builder()
+    ///     .text("second")
+    ///     .uri("https://www.example.com")
+    ///     .group(&group_id)
+    ///     .build()?).await?;
+    /// let annotations = api.annotations_after(&first.id, &group_id).await?;
+    /// assert!(annotations.iter().all(|annotation| annotation.id != first.id));
+    /// #     api.delete_annotations(&[first.id, second.id]).await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn annotations_after(
+        &self,
+        last_id: &str,
+        group: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        if group.trim().is_empty() {
+            return Err(HypothesisError::UnboundedSearch);
+        }
+        let last = self.fetch_annotation(last_id).await?;
+        self.sync_since(last.updated, group).await
+    }
+
+    /// Lazily retrieve all annotations matching query as a stream
+    ///
+    /// Pages through the results using `search_after` so that only one page of annotations
+    /// is held in memory at a time. Like `search_annotations_return_all`, results are sorted
+    /// by ascending `updated` date.
+    /// See  [`SearchQuery`](annotations/struct.SearchQuery.html) for filtering options,
+    /// `since` and `until` are handled the same way as in `search_annotations_return_all`.
+    /// Unbounded queries also need [`SearchQuery::allow_unbounded`], otherwise the stream
+    /// yields [`HypothesisError::UnboundedSearch`] without sending a request.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use futures::StreamExt;
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let search_query = SearchQuery::builder().user(&api.user.0).build()?;
+    /// let stream = api.search_annotations_stream(search_query);
+    /// futures::pin_mut!(stream);
+    /// while let Some(annotation) = stream.next().await {
+    ///     assert_eq!(annotation?.user, api.user);
+    /// }
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn search_annotations_stream(
+        &self,
+        mut query: SearchQuery,
+    ) -> impl Stream<Item = Result<Annotation, HypothesisError>> + '_ {
+        query.prepare_pagination();
+        stream::try_unfold(Some(query), move |query| async move {
+            let mut query = match query {
+                Some(query) => query,
+                None => return Ok(None),
+            };
+            if query.is_unbounded() && !query.allow_unbounded {
+                return Err(HypothesisError::UnboundedSearch);
+            }
+            let (mut page, _) = self.search_page(&query).await?;
+            let next = match page.last() {
+                Some(last) => {
+                    query.search_after = last.updated.to_rfc3339();
+                    let end = query.until_index(&page);
+                    if let Some(end) = end {
+                        page.truncate(end);
+                    }
+                    page.retain(|annotation| query.matches_tags_any(annotation));
+                    let query = match end {
+                        Some(_) => None,
+                        None => Some(query),
+                    };
+                    Some((
+                        stream::iter(page.into_iter().map(Ok::<_, HypothesisError>)),
+                        query,
+                    ))
+                }
+                None => None,
+            };
+            Ok::<_, HypothesisError>(next)
+        })
+        .try_flatten()
+    }
+
+    /// Retrieve at most `n` annotations matching query, e.g. to preview a large result set
+    ///
+    /// Pages through the results like `search_annotations_stream` and stops requesting pages
+    /// once `n` annotations were found, so results are sorted by ascending `updated` date.
+    /// The query's `limit` is lowered to `n` if it's larger, to avoid fetching more than needed.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::SearchQuery;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let search_query = SearchQuery::builder().user(&api.user.0).limit(2).build()?;
+    /// let annotations = api.search_annotations_take(&search_query, 3).await?;
+    /// assert!(annotations.len() <= 3);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn search_annotations_take(
+        &self,
+        query: &SearchQuery,
+        n: usize,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        let mut query = query.clone();
+        query.limit = query.limit.min(n.try_into().unwrap_or(u8::MAX));
+        // bounded by `n`
+        query.allow_unbounded = true;
+        self.search_annotations_stream(query)
+            .take(n)
+            .try_collect()
+            .await
+    }
+
+    /// Fetch annotation by ID
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #                       .text("string")
+    /// #                       .uri("http://example.com")
+    /// #                       .group(group_id).build()?).await?;
+    /// #    let annotation_id = annotation.id.to_owned();    
+    /// let annotation = api.fetch_annotation(&annotation_id).await?;
+    /// assert_eq!(annotation.id, annotation_id);
+    /// #    api.delete_annotation(&annotation.id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_annotation(&self, id: &str) -> Result<Annotation, HypothesisError> {
+        let text = self
+            .get_cached(&format!("{}/annotations/{}", self.base_url, id))
+            .await?;
+        serde_parse::<Annotation>(&text)
+    }
+
+    /// Fetch an annotation by ID as untyped JSON, including fields `Annotation` doesn't have
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #                       .text("string")
+    /// #                       .uri("http://example.com")
+    /// #                       .group(group_id).build()?).await?;
+    /// #    let annotation_id = annotation.id.to_owned();
+    /// let json = api.fetch_annotation_raw(&annotation_id).await?;
+    /// assert_eq!(json["text"], "string");
+    /// #    api.delete_annotation(&annotation_id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_annotation_raw(
+        &self,
+        id: &str,
+    ) -> Result<serde_json::Value, HypothesisError> {
+        let text = self
+            .get_cached(&format!("{}/annotations/{}", self.base_url, id))
+            .await?;
+        serde_parse::<serde_json::Value>(&text)
+    }
+
+    /// Check whether an annotation exists (and is readable by the user), without downloading it
+    ///
+    /// Returns `false` if the API responds with 404 Not Found, other errors are returned as is.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #                       .text("string")
+    /// #                       .uri("http://example.com")
+    /// #                       .build()?).await?;
+    /// assert!(api.annotation_exists(&annotation.id).await?);
+    /// api.delete_annotation(&annotation.id).await?;
+    /// assert!(!api.annotation_exists(&annotation.id).await?);
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn annotation_exists(&self, id: &str) -> Result<bool, HypothesisError> {
+        let result = self
+            .send(
+                self.client
+                    .head(&format!("{}/annotations/{}", self.base_url, id)),
+            )
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(error) if error.is_not_found() => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Fetch whether an annotation is hidden and how often it was flagged
+    ///
+    /// The flag count is only available to moderators of the annotation's group, otherwise it's `None`.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #                       .text("string")
+    /// #                       .uri("http://example.com")
+    /// #                       .group(group_id).build()?).await?;
+    /// #    let annotation_id = annotation.id.to_owned();
+    /// let status = api.annotation_moderation_status(&annotation_id).await?;
+    /// assert!(!status.hidden);
+    /// #    api.delete_annotation(&annotation_id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn annotation_moderation_status(
+        &self,
+        id: &str,
+    ) -> Result<ModerationStatus, HypothesisError> {
+        let text = self
+            .send(
+                self.client
+                    .get(&format!("{}/annotations/{}", self.base_url, id)),
+            )
+            .await?;
+        #[derive(Deserialize, Debug, Clone, PartialEq)]
+        struct ModerationResult {
+            #[serde(default)]
+            hidden: bool,
+            #[serde(default)]
+            moderation: Option<Moderation>,
+        }
+        let result = serde_parse::<ModerationResult>(&text)?;
+        Ok(ModerationStatus {
+            hidden: result.hidden,
+            flag_count: result.moderation.map(|moderation| moderation.flag_count),
+        })
+    }
+
+    /// Fetch all replies to an annotation
+    ///
+    /// Replies store the IDs of all their ancestors in `references`,
+    /// so this also includes replies to replies.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let annotation = api.create_annotation(&InputAnnotation::builder()
+    ///                     .text("string")
+    ///                     .uri("http://example.com")
+    ///                     .group(&group_id).build()?).await?;
+    /// let reply = api.create_annotation(&InputAnnotation::builder()
+    ///                     .text("reply")
+    ///                     .uri("http://example.com")
+    ///                     .references(vec![annotation.id.clone()])
+    ///                     .build()?).await?;
+    /// # std::thread::sleep(std::time::Duration::from_millis(500));
+    /// let replies = api.fetch_replies(&annotation.id).await?;
+    /// assert_eq!(replies[0].id, reply.id);
+    /// #    api.delete_annotations(&[reply.id, annotation.id]).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_replies(
+        &self,
+        annotation_id: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let mut query = SearchQuery::builder()
+            .references(annotation_id)
+            .limit(200)
+            .build()?;
+        self.search_annotations_return_all(&mut query).await
+    }
+
+    /// Fetch an annotation along with all its (nested) replies, sorted by creation date
+    pub async fn fetch_thread(
+        &self,
+        annotation_id: &str,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let (annotation, replies) = futures::try_join!(
+            self.fetch_annotation(annotation_id),
+            self.fetch_replies(annotation_id)
+        )?;
+        let mut thread = vec![annotation];
+        thread.extend(replies);
+        thread.sort_by(Annotation::cmp_by_created);
+        Ok(thread)
+    }
+
+    /// Delete annotation by ID
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #                       .text("string")
+    /// #                       .uri("http://example.com")
+    /// #                       .group(group_id).build()?).await?;
+    /// #    let annotation_id = annotation.id.to_owned();    
+    /// let deleted = api.delete_annotation(&annotation_id).await?;
+    /// assert!(deleted);
+    /// assert!(api.fetch_annotation(&annotation_id).await.unwrap_err().is_not_found());
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn delete_annotation(&self, id: &str) -> Result<bool, HypothesisError> {
+        let text = self
+            .send(
+                self.client
+                    .delete(&format!("{}/annotations/{}", self.base_url, id)),
+            )
+            .await?;
         #[derive(Deserialize, Debug, Clone, PartialEq)]
         struct DeletionResult {
             id: String,
@@ -526,10 +1664,59 @@ impl Hypothesis {
         Ok(serde_parse::<DeletionResult>(&text)?.deleted)
     }
 
-    /// Delete multiple annotations by ID
-    pub async fn delete_annotations(&self, ids: &[String]) -> Result<Vec<bool>, HypothesisError> {
-        let futures: Vec<_> = ids.iter().map(|id| self.delete_annotation(id)).collect();
-        async { try_join_all(futures).await }.await
+    /// Move an annotation to another group
+    ///
+    /// The API doesn't allow changing an annotation's group, so this creates a copy in `to_group`
+    /// and deletes the original. The moved annotation gets a new ID and `created` date,
+    /// and replies to the original keep referencing the old ID.
+    /// If the original can't be deleted, the copy is kept and returned in a
+    /// [`HypothesisError::MoveIncomplete`], so retries can delete it or the original.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #    use hypothesis::annotations::InputAnnotation;
+    /// #    dotenvy::dotenv()?;
+    /// #    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #    let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// #    let annotation = api.create_annotation(&InputAnnotation::builder()
+    /// #                       .text("string")
+    /// #                       .uri("http://example.com")
+    /// #                       .group("__world__").build()?).await?;
+    /// let moved = api.move_annotation(&annotation.id, &group_id).await?;
+    /// assert_eq!(moved.group, group_id);
+    /// assert_eq!(moved.text, annotation.text);
+    /// #    api.delete_annotation(&moved.id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn move_annotation(
+        &self,
+        id: &str,
+        to_group: &str,
+    ) -> Result<Annotation, HypothesisError> {
+        let annotation = self.fetch_annotation(id).await?;
+        if annotation.group == to_group {
+            return Ok(annotation);
+        }
+        let moved = self
+            .create_annotation(&annotation.to_input(Some(to_group)))
+            .await?;
+        match self.delete_annotation(id).await {
+            Ok(true) => Ok(moved),
+            Ok(false) => Err(HypothesisError::MoveIncomplete {
+                moved: Box::new(moved),
+                source: None,
+            }),
+            Err(error) => Err(HypothesisError::MoveIncomplete {
+                moved: Box::new(moved),
+                source: Some(Box::new(error)),
+            }),
+        }
     }
 
     /// Flag an annotation
@@ -538,25 +1725,12 @@ impl Hypothesis {
     /// annotation will be notified of the flag and can decide whether or not to hide the
     /// annotation. Note that flags persist and cannot be removed once they are set.
     pub async fn flag_annotation(&self, id: &str) -> Result<(), HypothesisError> {
-        let text = self
-            .client
-            .put(&format!("{}/annotations/{}/flag", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        let error = serde_json::from_str::<errors::APIError>(&text);
-        if let Ok(error) = error {
-            Err(HypothesisError::APIError {
-                source: error,
-                raw_text: text,
-                serde_error: None,
-            })
-        } else {
-            Ok(())
-        }
+        self.send(
+            self.client
+                .put(&format!("{}/annotations/{}/flag", self.base_url, id)),
+        )
+        .await?;
+        Ok(())
     }
 
     /// Hide an annotation
@@ -564,25 +1738,12 @@ impl Hypothesis {
     /// Hide an annotation. The authenticated user needs to have the moderate permission for the
     /// group that contains the annotation — this permission is granted to the user who created the group.
     pub async fn hide_annotation(&self, id: &str) -> Result<(), HypothesisError> {
-        let text = self
-            .client
-            .put(&format!("{}/annotations/{}/hide", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        let error = serde_json::from_str::<errors::APIError>(&text);
-        if let Ok(error) = error {
-            Err(HypothesisError::APIError {
-                source: error,
-                raw_text: text,
-                serde_error: None,
-            })
-        } else {
-            Ok(())
-        }
+        self.send(
+            self.client
+                .put(&format!("{}/annotations/{}/hide", self.base_url, id)),
+        )
+        .await?;
+        Ok(())
     }
 
     /// Show an annotation
@@ -590,24 +1751,40 @@ impl Hypothesis {
     /// Show/"un-hide" an annotation. The authenticated user needs to have the moderate permission
     /// for the group that contains the annotation—this permission is granted to the user who created the group.
     pub async fn show_annotation(&self, id: &str) -> Result<(), HypothesisError> {
-        let text = self
-            .client
-            .delete(&format!("{}/annotations/{}/hide", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        let error = serde_json::from_str::<errors::APIError>(&text);
-        if let Ok(error) = error {
-            Err(HypothesisError::APIError {
-                source: error,
-                raw_text: text,
-                serde_error: None,
-            })
-        } else {
-            Ok(())
+        self.send(
+            self.client
+                .delete(&format!("{}/annotations/{}/hide", self.base_url, id)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Hide, show or flag an annotation, see `hide_annotation`, `show_annotation` and `flag_annotation`
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::ModerationAction;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let annotation_id = String::from("annotation_id");
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// api.moderate_annotation(&annotation_id, ModerationAction::Hide).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn moderate_annotation(
+        &self,
+        id: &str,
+        action: ModerationAction,
+    ) -> Result<(), HypothesisError> {
+        match action {
+            ModerationAction::Hide => self.hide_annotation(id).await,
+            ModerationAction::Show => self.show_annotation(id).await,
+            ModerationAction::Flag => self.flag_annotation(id).await,
         }
     }
 
@@ -620,9 +1797,9 @@ impl Hypothesis {
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
     /// use hypothesis::groups::GroupFilters;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     ///
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// /// Get all Groups belonging to user
@@ -632,27 +1809,10 @@ impl Hypothesis {
     /// # }
     /// ```
     pub async fn get_groups(&self, query: &GroupFilters) -> Result<Vec<Group>, HypothesisError> {
-        let query: HashMap<String, serde_json::Value> = serde_json::from_str(
-            &serde_json::to_string(&query).map_err(HypothesisError::SerdeError)?,
-        )
-        .map_err(HypothesisError::SerdeError)?;
-        let url = Url::parse_with_params(
-            &format!("{}/groups", API_URL),
-            query
-                .into_iter()
-                .map(|(k, v)| (k, v.to_string().replace('"', "")))
-                .collect::<Vec<_>>(),
-        )
-        .map_err(HypothesisError::URLError)?;
-        let text = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+        let url =
+            Url::parse_with_params(&format!("{}/groups", self.base_url), query_params(query)?)
+                .map_err(HypothesisError::URLError)?;
+        let text = self.send(self.client.get(url)).await?;
         serde_parse::<Vec<Group>>(&text)
     }
 
@@ -663,9 +1823,9 @@ impl Hypothesis {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     ///
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let group = api.create_group("my_group", Some("a test group")).await?;
@@ -677,36 +1837,50 @@ impl Hypothesis {
         name: &str,
         description: Option<&str>,
     ) -> Result<Group, HypothesisError> {
-        let mut params = HashMap::new();
-        params.insert("name", name);
-        if let Some(description) = description {
-            params.insert("description", description);
-        }
-        let text = self
-            .client
-            .post(&format!("{}/groups", API_URL))
-            .json(&params)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        serde_parse::<Group>(&text)
+        self.create_group_full(&CreateGroupOptions {
+            name: name.into(),
+            description: description.map(String::from),
+            groupid: None,
+            group_type: None,
+            scopes: None,
+        })
+        .await
     }
 
-    /// Create multiple groups
-    pub async fn create_groups(
+    /// Create a new group with a type, scope or custom group ID.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::groups::{CreateGroupOptions, Type};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    ///
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let options = CreateGroupOptions::builder()
+    ///     .name("my_group")
+    ///     .group_type(Type::Restricted)
+    ///     .uri_patterns(vec!["https://example.com/*".to_string()])
+    ///     .build()?;
+    /// let group = api.create_group_full(&options).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn create_group_full(
         &self,
-        names: &[String],
-        descriptions: &[Option<String>],
-    ) -> Result<Vec<Group>, HypothesisError> {
-        let futures: Vec<_> = names
-            .iter()
-            .zip(descriptions.iter())
-            .map(|(name, description)| self.create_group(name, description.as_deref()))
-            .collect();
-        async { try_join_all(futures).await }.await
+        options: &CreateGroupOptions,
+    ) -> Result<Group, HypothesisError> {
+        let text = self
+            .send(
+                self.client
+                    .post(&format!("{}/groups", self.base_url))
+                    .json(options),
+            )
+            .await?;
+        serde_parse::<Group>(&text)
     }
 
     /// Fetch a single Group resource.
@@ -716,15 +1890,16 @@ impl Hypothesis {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// use hypothesis::groups::Expand;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #     let group_id = dotenv::var("TEST_GROUP_ID")?;
+    /// use hypothesis::groups::{Expand, Organization};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
     ///
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// /// Expands organization into a struct
     /// let group = api.fetch_group(&group_id, vec![Expand::Organization]).await?;
+    /// assert!(matches!(group.organization, Organization::Organization(_)));
     /// #    Ok(())
     /// # }    
     /// ```
@@ -733,45 +1908,53 @@ impl Hypothesis {
         id: &str,
         expand: Vec<Expand>,
     ) -> Result<Group, HypothesisError> {
-        let params: HashMap<&str, Vec<String>> = if !expand.is_empty() {
-            vec![(
-                "expand",
-                expand
-                    .into_iter()
-                    .map(|e| serde_json::to_string(&e))
-                    .collect::<Result<_, _>>()
-                    .map_err(HypothesisError::SerdeError)?,
-            )]
-            .into_iter()
-            .collect()
-        } else {
-            HashMap::new()
-        };
-        let text = self
-            .client
-            .get(&format!("{}/groups/{}", API_URL, id))
-            .json(&params)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+        let params = expand
+            .iter()
+            .map(|e| serde_json::to_string(e).map(|e| ("expand", e.replace('"', ""))))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(HypothesisError::SerdeError)?;
+        let url = Url::parse_with_params(&format!("{}/groups/{}", self.base_url, id), params)
+            .map_err(HypothesisError::URLError)?;
+        let text = self.get_cached(url.as_str()).await?;
         serde_parse::<Group>(&text)
     }
 
-    /// Fetch multiple groups by ID
-    pub async fn fetch_groups(
+    /// Fetch a single Group resource by the `groupid` assigned by its authority (e.g. an LMS),
+    /// instead of the Hypothesis `id`.
+    ///
+    /// Takes either the full `groupid` ("group:{id}@{authority}") as returned in [`Group`](groups/struct.Group.html)
+    /// or just the ID, which is then looked up within the client's [authority](#method.authority).
+    /// If there's no such group, the API responds with 404 (see [`HypothesisError::is_not_found`](errors/enum.HypothesisError.html#method.is_not_found)).
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::builder()
+    ///     .username(&username)
+    ///     .developer_key(&developer_key)
+    ///     .authority("lms.example.com")
+    ///     .build()?;
+    /// let group = api.fetch_group_by_groupid("course-101", vec![]).await?;
+    /// assert_eq!(group.groupid.as_deref(), Some("group:course-101@lms.example.com"));
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_group_by_groupid(
         &self,
-        ids: &[String],
-        expands: Vec<Vec<Expand>>,
-    ) -> Result<Vec<Group>, HypothesisError> {
-        let futures: Vec<_> = ids
-            .iter()
-            .zip(expands.into_iter())
-            .map(|(id, expand)| self.fetch_group(id, expand))
-            .collect();
-        async { try_join_all(futures).await }.await
+        groupid: &str,
+        expand: Vec<Expand>,
+    ) -> Result<Group, HypothesisError> {
+        if groupid.starts_with("group:") {
+            self.fetch_group(groupid, expand).await
+        } else {
+            self.fetch_group(&format!("group:{}@{}", groupid, self.authority), expand)
+                .await
+        }
     }
 
     /// Update a Group resource.
@@ -781,10 +1964,10 @@ impl Hypothesis {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #     let group_id = dotenv::var("TEST_GROUP_ID")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
     ///
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let group = api.update_group(&group_id, Some("new_group_name"), None).await?;
@@ -807,34 +1990,48 @@ impl Hypothesis {
             params.insert("description", description);
         }
         let text = self
-            .client
-            .patch(&format!("{}/groups/{}", API_URL, id))
-            .json(&params)
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+            .send(
+                self.client
+                    .patch(&format!("{}/groups/{}", self.base_url, id))
+                    .json(&params),
+            )
+            .await?;
         serde_parse::<Group>(&text)
     }
 
-    /// Update multiple groups
-    pub async fn update_groups(
-        &self,
-        ids: &[String],
-        names: &[Option<String>],
-        descriptions: &[Option<String>],
-    ) -> Result<Vec<Group>, HypothesisError> {
-        let futures: Vec<_> = ids
-            .iter()
-            .zip(names.iter())
-            .zip(descriptions.iter())
-            .map(|((id, name), description)| {
-                self.update_group(id, name.as_deref(), description.as_deref())
-            })
-            .collect();
-        async { try_join_all(futures).await }.await
+    /// Delete a group. Only the group's creator can delete it.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let group = api.create_group("throwaway_group", None).await?;
+    /// let deleted = api.delete_group(&group.id).await?;
+    /// assert!(deleted);
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn delete_group(&self, id: &str) -> Result<bool, HypothesisError> {
+        let text = self
+            .send(
+                self.client
+                    .delete(&format!("{}/groups/{}", self.base_url, id)),
+            )
+            .await?;
+        // the API responds with 204 No Content on success
+        if text.trim().is_empty() {
+            return Ok(true);
+        }
+        #[derive(Deserialize, Debug, Clone, PartialEq)]
+        struct DeletionResult {
+            deleted: bool,
+        }
+        Ok(serde_parse::<DeletionResult>(&text)?.deleted)
     }
 
     /// Fetch a list of all members (users) in a group. Returned user resource only contains public-facing user data.
@@ -846,10 +2043,10 @@ impl Hypothesis {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
-    /// #     let group_id = dotenv::var("TEST_GROUP_ID")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
     ///
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let members = api.get_group_members(&group_id).await?;
@@ -858,38 +2055,205 @@ impl Hypothesis {
     /// ```
     pub async fn get_group_members(&self, id: &str) -> Result<Vec<Member>, HypothesisError> {
         let text = self
-            .client
-            .get(&format!("{}/groups/{}/members", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+            .send(
+                self.client
+                    .get(&format!("{}/groups/{}/members", self.base_url, id)),
+            )
+            .await?;
         serde_parse::<Vec<Member>>(&text)
     }
 
+    /// Fetch a page of members (users) in a group, optionally filtered by role.
+    ///
+    /// Returns the members along with the total number of (matching) members in the group,
+    /// if the API reports it.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::groups::MemberFilters;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let filters = MemberFilters {
+    ///     page_size: Some(10),
+    ///     page_number: Some(1),
+    ///     ..Default::default()
+    /// };
+    /// let (members, total) = api.get_group_members_filtered(&group_id, &filters).await?;
+    /// assert!(members.len() <= 10);
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn get_group_members_filtered(
+        &self,
+        id: &str,
+        filters: &MemberFilters,
+    ) -> Result<(Vec<Member>, Option<usize>), HypothesisError> {
+        let url = Url::parse_with_params(
+            &format!("{}/groups/{}/members", self.base_url, id),
+            query_params(filters)?,
+        )
+        .map_err(HypothesisError::URLError)?;
+        let text = self.send(self.client.get(url)).await?;
+        #[derive(Deserialize, Debug, Clone, PartialEq)]
+        struct Page {
+            total: usize,
+        }
+        #[derive(Deserialize, Debug, Clone, PartialEq)]
+        struct Meta {
+            page: Page,
+        }
+        #[derive(Deserialize, Debug, Clone, PartialEq)]
+        #[serde(untagged)]
+        enum MembersResult {
+            Paginated { meta: Meta, data: Vec<Member> },
+            List(Vec<Member>),
+        }
+        Ok(match serde_parse::<MembersResult>(&text)? {
+            MembersResult::Paginated { meta, data } => (data, Some(meta.page.total)),
+            MembersResult::List(members) => (members, None),
+        })
+    }
+
+    /// Lazily retrieve the members of a group as a stream
+    ///
+    /// Requests pages of members with `get_group_members_filtered` as the stream is consumed,
+    /// so you can stop early once you find a member. If the API doesn't paginate members,
+    /// all of them are fetched in the first request.
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use futures::TryStreamExt;
+    /// use hypothesis::Hypothesis;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let stream = api.get_group_members_stream(&group_id);
+    /// futures::pin_mut!(stream);
+    /// let first = stream.try_next().await?;
+    /// assert!(first.is_some());
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub fn get_group_members_stream<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> impl Stream<Item = Result<Member, HypothesisError>> + 'a {
+        // next page number and number of members seen so far, `None` once all pages were fetched
+        stream::try_unfold(Some((1, 0)), move |state| async move {
+            let (page_number, seen) = match state {
+                Some(state) => state,
+                None => return Ok(None),
+            };
+            let filters = MemberFilters {
+                page_size: Some(MEMBERS_PAGE_SIZE),
+                page_number: Some(page_number),
+                ..Default::default()
+            };
+            let (members, total) = self.get_group_members_filtered(id, &filters).await?;
+            if members.is_empty() {
+                return Ok(None);
+            }
+            let seen = seen + members.len();
+            let next = match total {
+                Some(total) if seen < total => Some((page_number + 1, seen)),
+                _ => None,
+            };
+            Ok::<_, HypothesisError>(Some((
+                stream::iter(members.into_iter().map(Ok::<_, HypothesisError>)),
+                next,
+            )))
+        })
+        .try_flatten()
+    }
+
+    /// Add a user to a group.
+    ///
+    /// The authenticated user needs to be allowed to manage the group's members.
+    /// Fails with a 404 [`HypothesisError::APIError`] if the group or user doesn't exist
+    /// and a 409 if the user's authority doesn't match the group's.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::{Hypothesis, UserAccountID};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let new_member = "collaborator".parse::<UserAccountID>()?;
+    /// api.add_group_member(&group_id, &new_member).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn add_group_member(
+        &self,
+        group_id: &str,
+        userid: &UserAccountID,
+    ) -> Result<(), HypothesisError> {
+        self.send(self.client.post(&format!(
+            "{}/groups/{}/members/{}",
+            self.base_url, group_id, userid
+        )))
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a user from a group.
+    ///
+    /// The authenticated user needs to be allowed to manage the group's members,
+    /// use `leave_group` to remove yourself.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::{Hypothesis, UserAccountID};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let member = "collaborator".parse::<UserAccountID>()?;
+    /// api.add_group_member(&group_id, &member).await?;
+    /// assert!(api.get_group_members(&group_id).await?.iter().any(|m| m.userid == member.0));
+    /// api.remove_group_member(&group_id, &member).await?;
+    /// assert!(!api.get_group_members(&group_id).await?.iter().any(|m| m.userid == member.0));
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub async fn remove_group_member(
+        &self,
+        group_id: &str,
+        userid: &UserAccountID,
+    ) -> Result<(), HypothesisError> {
+        self.send(self.client.delete(&format!(
+            "{}/groups/{}/members/{}",
+            self.base_url, group_id, userid
+        )))
+        .await?;
+        Ok(())
+    }
+
     /// Remove yourself from a group.
     pub async fn leave_group(&self, id: &str) -> Result<(), HypothesisError> {
-        let text = self
-            .client
-            .delete(&format!("{}/groups/{}/members/me", API_URL, id))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
-        let error = serde_json::from_str::<errors::APIError>(&text);
-        if let Ok(error) = error {
-            Err(HypothesisError::APIError {
-                source: error,
-                raw_text: text,
-                serde_error: None,
-            })
-        } else {
-            Ok(())
-        }
+        self.send(
+            self.client
+                .delete(&format!("{}/groups/{}/members/me", self.base_url, id)),
+        )
+        .await?;
+        Ok(())
     }
 
     /// Fetch profile information for the currently-authenticated user.
@@ -899,9 +2263,9 @@ impl Hypothesis {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let profile = api.fetch_user_profile().await?;
     /// assert!(profile.userid.is_some());
@@ -912,14 +2276,8 @@ impl Hypothesis {
 
     pub async fn fetch_user_profile(&self) -> Result<UserProfile, HypothesisError> {
         let text = self
-            .client
-            .get(&format!("{}/profile", API_URL))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+            .send(self.client.get(&format!("{}/profile", self.base_url)))
+            .await?;
         serde_parse::<UserProfile>(&text)
     }
 
@@ -929,39 +2287,285 @@ impl Hypothesis {
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// use hypothesis::Hypothesis;
-    /// #     dotenv::dotenv()?;
-    /// #     let username = dotenv::var("HYPOTHESIS_NAME")?;
-    /// #     let developer_key = dotenv::var("HYPOTHESIS_KEY")?;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
     /// let api = Hypothesis::new(&username, &developer_key)?;
     /// let groups = api.fetch_user_groups().await?;
     /// #     Ok(())
     /// # }
     /// ```
     pub async fn fetch_user_groups(&self) -> Result<Vec<Group>, HypothesisError> {
-        let text = self
-            .client
-            .get(&format!("{}/profile/groups", API_URL))
-            .send()
-            .await
-            .map_err(HypothesisError::ReqwestError)?
-            .text()
-            .await
-            .map_err(HypothesisError::ReqwestError)?;
+        self.fetch_user_groups_expanded(Vec::new()).await
+    }
+
+    /// Fetch the groups for which the currently-authenticated user is a member,
+    /// expanding their organization, scopes, or both.
+    ///
+    /// The endpoint returns all groups at once, it doesn't support paging.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::groups::Expand;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let groups = api.fetch_user_groups_expanded(vec![Expand::Scopes]).await?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_user_groups_expanded(
+        &self,
+        expand: Vec<Expand>,
+    ) -> Result<Vec<Group>, HypothesisError> {
+        let params = expand
+            .iter()
+            .map(|e| serde_json::to_string(e).map(|e| ("expand", e.replace('"', ""))))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(HypothesisError::SerdeError)?;
+        let url = Url::parse_with_params(&format!("{}/profile/groups", self.base_url), params)
+            .map_err(HypothesisError::URLError)?;
+        let text = self.send(self.client.get(url)).await?;
         serde_parse::<Vec<Group>>(&text)
     }
+
+    /// Find the user's groups by name, ignoring case
+    ///
+    /// Searches the groups from `fetch_user_groups`, either by the whole name or
+    /// by part of it depending on `name_match`.
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::groups::NameMatch;
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let groups = api.find_groups_by_name("public", NameMatch::Exact).await?;
+    /// assert!(groups.iter().any(|group| group.id == "__world__"));
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub async fn find_groups_by_name(
+        &self,
+        name: &str,
+        name_match: NameMatch,
+    ) -> Result<Vec<Group>, HypothesisError> {
+        let mut groups = self.fetch_user_groups().await?;
+        groups.retain(|group| name_match.matches(&group.name, name));
+        Ok(groups)
+    }
+}
+
+/// Builder for the [`Hypothesis`] client
+///
+/// `username` and `developer_key` are required, everything else is optional.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), hypothesis::errors::HypothesisError> {
+/// use hypothesis::Hypothesis;
+/// use std::time::Duration;
+/// let api = Hypothesis::builder()
+///     .username("my_username")
+///     .developer_key("my_developer_key")
+///     .base_url("https://hypothesis.example.com/api")
+///     .authority("example.com")
+///     .timeout(Duration::from_secs(10))
+///     .max_retries(5)
+///     .build()?;
+/// assert_eq!(api.user.0, "acct:my_username@example.com");
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HypothesisBuilder {
+    username: Option<String>,
+    developer_key: Option<String>,
+    base_url: String,
+    authority: String,
+    max_retries: u32,
+    retry_delay: Duration,
+    retry_non_idempotent: bool,
+    max_concurrency: usize,
+    timeout: Option<Duration>,
+    access_token: Option<String>,
+    api_version: ApiVersion,
+    etag_cache: bool,
+}
+
+impl Default for HypothesisBuilder {
+    fn default() -> Self {
+        Self {
+            username: None,
+            developer_key: None,
+            base_url: API_URL.into(),
+            authority: DEFAULT_AUTHORITY.into(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            retry_non_idempotent: false,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            timeout: Some(DEFAULT_TIMEOUT),
+            access_token: None,
+            api_version: ApiVersion::default(),
+            etag_cache: false,
+        }
+    }
+}
+
+impl HypothesisBuilder {
+    /// Hypothesis username
+    pub fn username<S: Into<String>>(&mut self, username: S) -> &mut Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Personal developer API key
+    pub fn developer_key<S: Into<String>>(&mut self, developer_key: S) -> &mut Self {
+        self.developer_key = Some(developer_key.into());
+        self
+    }
+
+    /// OAuth access token, used instead of a developer key by apps using the OAuth flow.
+    ///
+    /// Ignored if `developer_key` is set.
+    pub fn access_token<S: Into<String>>(&mut self, access_token: S) -> &mut Self {
+        self.access_token = Some(access_token.into());
+        self
+    }
+
+    /// API URL to send requests to, defaults to [`API_URL`]
+    pub fn base_url<S: Into<String>>(&mut self, base_url: S) -> &mut Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Authority the user's account belongs to, defaults to [`DEFAULT_AUTHORITY`].
+    /// Needed for accounts on self-hosted instances or publisher authorities.
+    pub fn authority<S: Into<String>>(&mut self, authority: S) -> &mut Self {
+        self.authority = authority.into();
+        self
+    }
+
+    /// Number of times a request is retried on connection errors and 5xx responses,
+    /// defaults to [`DEFAULT_MAX_RETRIES`]. Set to 0 to disable retries.
+    pub fn max_retries(&mut self, max_retries: u32) -> &mut Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled after each attempt,
+    /// defaults to [`DEFAULT_RETRY_DELAY`]
+    pub fn retry_delay(&mut self, retry_delay: Duration) -> &mut Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Also retry POST requests (e.g. `create_annotation`).
+    ///
+    /// Off by default since a request that failed after reaching the server may be
+    /// retried and create a duplicate.
+    pub fn retry_non_idempotent(&mut self, retry_non_idempotent: bool) -> &mut Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    /// Maximum number of requests bulk operations (e.g. `create_annotations`) keep in flight
+    /// at once, defaults to [`DEFAULT_MAX_CONCURRENCY`]. Values below 1 are treated as 1.
+    pub fn max_concurrency(&mut self, max_concurrency: usize) -> &mut Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    /// Timeout for each request, from connecting until the response body is read,
+    /// defaults to [`DEFAULT_TIMEOUT`].
+    /// Requests that time out fail with [`HypothesisError::Timeout`](errors/enum.HypothesisError.html#variant.Timeout).
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Never time out requests
+    pub fn no_timeout(&mut self) -> &mut Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Cache the responses of `fetch_annotation` and `fetch_group` and send their ETag with
+    /// `If-None-Match`, so that unchanged resources aren't downloaded again. Off by default.
+    ///
+    /// Has no effect if the API doesn't send an `ETag` header.
+    pub fn etag_cache(&mut self, etag_cache: bool) -> &mut Self {
+        self.etag_cache = etag_cache;
+        self
+    }
+
+    /// API version to request, defaults to [`ApiVersion::V1`]
+    pub fn api_version(&mut self, api_version: ApiVersion) -> &mut Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Builds a new `Hypothesis` client.
+    pub fn build(&self) -> Result<Hypothesis, HypothesisError> {
+        let username = self
+            .username
+            .as_deref()
+            .ok_or_else(|| HypothesisError::BuilderError("`username` must be initialized".into()))?
+            .trim();
+        let token = match (&self.developer_key, &self.access_token) {
+            (Some(developer_key), _) => developer_key,
+            (None, Some(access_token)) => access_token,
+            (None, None) => {
+                return Err(HypothesisError::BuilderError(
+                    "`developer_key` or `access_token` must be initialized".into(),
+                ))
+            }
+        };
+        let user = UserAccountID::new(username, &self.authority);
+        let client = authorized_client(token, self.timeout, self.api_version)?;
+        Ok(Hypothesis {
+            username: username.into(),
+            user,
+            client,
+            base_url: self.base_url.trim_end_matches('/').into(),
+            authority: self.authority.clone(),
+            max_retries: self.max_retries,
+            retry_delay: self.retry_delay,
+            retry_non_idempotent: self.retry_non_idempotent,
+            max_concurrency: self.max_concurrency.max(1),
+            timeout: self.timeout,
+            api_version: self.api_version,
+            etag_cache: self
+                .etag_cache
+                .then(|| Arc::new(Mutex::new(HashMap::new()))),
+            rate_limit: Mutex::new(None),
+        })
+    }
 }
 
-/// Stores user account ID in the form "acct:{username}@hypothes.is"
+/// Stores user account ID in the form "acct:{username}@{authority}"
 ///
-/// Create from username:
+/// Create from username (with the hypothes.is authority):
 /// ```
 /// # use hypothesis::UserAccountID;
 /// let user_id = "my_username".parse::<UserAccountID>().unwrap();
+/// assert_eq!(user_id, UserAccountID::new("my_username", "hypothes.is"));
 /// ```
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct UserAccountID(pub String);
 
 impl UserAccountID {
+    /// Account ID of a user from the given authority
+    pub fn new(username: &str, authority: &str) -> Self {
+        Self(format!("acct:{}@{}", username, authority))
+    }
+
     pub fn to_username(&self) -> String {
         if self.0.len() < 5 {
             String::new()
@@ -984,7 +2588,7 @@ impl UserAccountID {
 impl FromStr for UserAccountID {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(format!("acct:{}@hypothes.is", s)))
+        Ok(Self::new(s, DEFAULT_AUTHORITY))
     }
 }
 
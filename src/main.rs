@@ -7,14 +7,16 @@ async fn main() -> color_eyre::Result<()> {
     use clap::Parser;
     use color_eyre::Help;
     use eyre::WrapErr;
-    use hypothesis::cli::HypothesisCLI;
+    use hypothesis::cli::{self, HypothesisCLI};
     use hypothesis::errors::CLIError;
-    use hypothesis::Hypothesis;
     color_eyre::install()?;
     let cli: HypothesisCLI = HypothesisCLI::parse();
-    let api = Hypothesis::from_env()
+    if let HypothesisCLI::Login = cli {
+        return cli::login();
+    }
+    let api = cli::client_from_env_or_config()
         .wrap_err(CLIError::AuthorizationError)
-        .suggestion("Make sure $HYPOTHESIS_NAME is set to your username and $HYPOTHESIS_KEY is set to your personal API key")?;
+        .suggestion("Run `hypothesis login`, or make sure $HYPOTHESIS_NAME is set to your username and $HYPOTHESIS_KEY is set to your personal API key")?;
     cli.run(api).await?;
     Ok(())
 }
@@ -11,9 +11,39 @@ use crate::UserAccountID;
 pub struct UserProfile {
     /// "hypothes.is"
     pub authority: String,
-    pub features: HashMap<String, bool>,
-    pub preferences: HashMap<String, bool>,
+    /// Feature flags enabled for the user
+    pub features: Features,
+    /// User interface preferences
+    pub preferences: Preferences,
     /// This property will be a string of the format "acct:username@authority" if the request is authenticated.
     /// This property will be null if the request is not authenticated.
     pub userid: Option<UserAccountID>,
 }
+
+/// Feature flags enabled for the user
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Features {
+    /// Add a cache-busting query parameter to the embedded client's URL
+    #[serde(default)]
+    pub embed_cachebuster: bool,
+    /// Search for annotations on a document by its DOI
+    #[serde(default)]
+    pub search_for_doi: bool,
+    /// Include `user_info` (e.g. display names) in API responses
+    #[serde(default)]
+    pub api_render_user_info: bool,
+    /// Any other feature flags
+    #[serde(flatten)]
+    pub extra: HashMap<String, bool>,
+}
+
+/// User interface preferences
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Preferences {
+    /// Show the tutorial panel when the sidebar is opened
+    #[serde(default)]
+    pub show_sidebar_tutorial: bool,
+    /// Any other preferences
+    #[serde(flatten)]
+    pub extra: HashMap<String, bool>,
+}
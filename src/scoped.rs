@@ -0,0 +1,124 @@
+//! A client scoped to one group, see [`Hypothesis::with_group`]
+use futures::Stream;
+
+use crate::annotations::{Annotation, InputAnnotation, SearchQuery};
+use crate::errors::HypothesisError;
+use crate::Hypothesis;
+
+/// Borrows a [`Hypothesis`] client and puts every created annotation in, and restricts every
+/// search to, one group, overriding the `group` set on the `InputAnnotation` or `SearchQuery`.
+///
+/// Use [`ScopedHypothesis::client`] for calls that don't take a group.
+#[derive(Clone, Copy)]
+pub struct ScopedHypothesis<'a> {
+    client: &'a Hypothesis,
+    group: &'a str,
+}
+
+impl Hypothesis {
+    /// Scope the client to a group, so annotations can't accidentally end up in the Public group
+    ///
+    /// # Example
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use hypothesis::Hypothesis;
+    /// use hypothesis::annotations::{InputAnnotation, SearchQuery};
+    /// #     dotenvy::dotenv()?;
+    /// #     let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    /// #     let developer_key = dotenvy::var("HYPOTHESIS_KEY")?;
+    /// #     let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or("__world__".into());
+    /// let api = Hypothesis::new(&username, &developer_key)?;
+    /// let scoped = api.with_group(&group_id);
+    /// let annotation = scoped.create_annotation(&InputAnnotation::builder()
+    ///     .text("string")
+    ///     .uri("http://example.com")
+    ///     .build()?).await?;
+    /// assert_eq!(annotation.group, group_id);
+    /// let query = SearchQuery::builder().user(&api.user.0).build()?;
+    /// let annotations = scoped.search_annotations(&query).await?;
+    /// assert!(annotations.iter().all(|annotation| annotation.group == group_id));
+    /// #    api.delete_annotation(&annotation.id).await?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    pub fn with_group<'a>(&'a self, group_id: &'a str) -> ScopedHypothesis<'a> {
+        ScopedHypothesis {
+            client: self,
+            group: group_id,
+        }
+    }
+}
+
+impl<'a> ScopedHypothesis<'a> {
+    /// The group ID this client is scoped to
+    pub fn group(&self) -> &str {
+        self.group
+    }
+
+    /// The underlying client
+    pub fn client(&self) -> &'a Hypothesis {
+        self.client
+    }
+
+    fn scope_input(&self, annotation: &InputAnnotation) -> InputAnnotation {
+        let mut annotation = annotation.clone();
+        annotation.group = self.group.to_string();
+        annotation
+    }
+
+    fn scope_query(&self, query: &SearchQuery) -> SearchQuery {
+        let mut query = query.clone();
+        query.group = vec![self.group.to_string()];
+        query
+    }
+
+    /// Create a new annotation in the group, see [`Hypothesis::create_annotation`]
+    pub async fn create_annotation(
+        &self,
+        annotation: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        self.client
+            .create_annotation(&self.scope_input(annotation))
+            .await
+    }
+
+    /// Create many new annotations in the group, see [`Hypothesis::create_annotations`]
+    pub async fn create_annotations(
+        &self,
+        annotations: &[InputAnnotation],
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let annotations: Vec<_> = annotations.iter().map(|a| self.scope_input(a)).collect();
+        self.client.create_annotations(&annotations).await
+    }
+
+    /// Search annotations in the group, see [`Hypothesis::search_annotations`]
+    pub async fn search_annotations(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        self.client
+            .search_annotations(&self.scope_query(query))
+            .await
+    }
+
+    /// Retrieve all annotations in the group matching query,
+    /// see [`Hypothesis::search_annotations_return_all`]
+    pub async fn search_annotations_return_all(
+        &self,
+        query: &mut SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        query.group = vec![self.group.to_string()];
+        self.client.search_annotations_return_all(query).await
+    }
+
+    /// Lazily retrieve all annotations in the group matching query as a stream,
+    /// see [`Hypothesis::search_annotations_stream`]
+    pub fn search_annotations_stream(
+        &self,
+        query: SearchQuery,
+    ) -> impl Stream<Item = Result<Annotation, HypothesisError>> + 'a {
+        self.client
+            .search_annotations_stream(self.scope_query(&query))
+    }
+}
@@ -0,0 +1,27 @@
+//! Text helpers for building and comparing quote selectors
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalizes the text of a quote (`TextQuoteSelector.exact`) so quotes extracted in different
+/// ways, e.g. from the DOM or a PDF's text layer, compare equal.
+///
+/// This is this crate's own normalization, not the one the Hypothesis client uses when anchoring.
+/// The result isn't necessarily found verbatim in the document.
+///
+/// The rules, applied in order:
+/// 1. Unicode NFC normalization, so composed and decomposed characters (e.g. "é" as one or two
+///    code points) are the same
+/// 2. Every run of whitespace (spaces, tabs, newlines, non-breaking spaces, ...) becomes a single space
+/// 3. Leading and trailing whitespace is removed
+///
+/// ```
+/// use hypothesis::text::normalize_quote;
+/// assert_eq!(normalize_quote("  exact\n\ttext\u{a0} "), "exact text");
+/// assert_eq!(normalize_quote("cafe\u{301}"), "caf\u{e9}");
+/// ```
+pub fn normalize_quote(text: &str) -> String {
+    text.nfc()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
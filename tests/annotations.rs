@@ -0,0 +1,530 @@
+use hypothesis::annotations::{
+    Annotation, CssSelector, DataPositionSelector, Document, EPUBContentSelector, FragmentSelector,
+    InputAnnotation, Moderation, PageSelector, Permissions, RangeSelector, SearchQuery,
+    SearchResult, Selector, SvgSelector, Target, TextPositionSelector, TextQuoteSelector,
+};
+use hypothesis::errors::HypothesisError;
+
+/// Annotation as returned by the API for a highlight made with the Hypothesis web client
+const WEB_CLIENT_ANNOTATION: &str = r#"{
+    "id": "Bvx0KJt2Ee-RVwMi3W2e8Q",
+    "created": "2024-10-26T10:32:15.361455+00:00",
+    "updated": "2024-10-26T10:32:15.361455+00:00",
+    "user": "acct:example_user@hypothes.is",
+    "uri": "https://www.example.com/",
+    "text": "a comment",
+    "tags": ["tag1"],
+    "group": "__world__",
+    "permissions": {
+        "read": ["group:__world__"],
+        "admin": ["acct:example_user@hypothes.is"],
+        "update": ["acct:example_user@hypothes.is"],
+        "delete": ["acct:example_user@hypothes.is"]
+    },
+    "target": [
+        {
+            "source": "https://www.example.com/",
+            "selector": [
+                {
+                    "type": "RangeSelector",
+                    "endOffset": 62,
+                    "startOffset": 0,
+                    "endContainer": "/div[1]/p[1]",
+                    "startContainer": "/div[1]/p[1]"
+                },
+                {
+                    "end": 100,
+                    "type": "TextPositionSelector",
+                    "start": 38
+                },
+                {
+                    "type": "TextQuoteSelector",
+                    "exact": "This domain is for use in illustrative examples in documents.",
+                    "prefix": "Example Domain\n    ",
+                    "suffix": " You may use this\n    domain in l"
+                }
+            ]
+        }
+    ],
+    "document": {"title": ["Example Domain"]},
+    "links": {
+        "html": "https://hypothes.is/a/Bvx0KJt2Ee-RVwMi3W2e8Q",
+        "incontext": "https://hyp.is/Bvx0KJt2Ee-RVwMi3W2e8Q/www.example.com/",
+        "json": "https://hypothes.is/api/annotations/Bvx0KJt2Ee-RVwMi3W2e8Q"
+    },
+    "user_info": {"display_name": "Example User"},
+    "flagged": false,
+    "hidden": false
+}"#;
+
+#[test]
+fn deserialize_range_selector() -> Result<(), serde_json::Error> {
+    let annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert_eq!(
+        annotation.target[0].selector[0],
+        Selector::RangeSelector(RangeSelector {
+            start_container: "/div[1]/p[1]".into(),
+            start_offset: 0,
+            end_container: "/div[1]/p[1]".into(),
+            end_offset: 62,
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn range_selector_ignores_extra_fields() -> Result<(), serde_json::Error> {
+    let selector: Selector = serde_json::from_str(
+        r#"{"type": "RangeSelector", "startContainer": "/p[1]", "startOffset": 1,
+            "endContainer": "/p[2]", "endOffset": 2, "unknown": true}"#,
+    )?;
+    assert!(matches!(selector, Selector::RangeSelector(_)));
+    Ok(())
+}
+
+#[test]
+fn range_selector_round_trip() -> Result<(), serde_json::Error> {
+    let annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    let serialized = serde_json::to_string(&annotation.target[0].selector[0])?;
+    assert!(serialized.contains(r#""type":"RangeSelector""#));
+    assert!(serialized.contains(r#""startContainer":"/div[1]/p[1]""#));
+    let selector: Selector = serde_json::from_str(&serialized)?;
+    assert_eq!(selector, annotation.target[0].selector[0]);
+    Ok(())
+}
+
+#[test]
+fn annotation_quote() -> Result<(), serde_json::Error> {
+    let annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert_eq!(
+        annotation.quote(),
+        Some("This domain is for use in illustrative examples in documents.")
+    );
+    assert_eq!(annotation.quotes().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn annotation_links() -> Result<(), serde_json::Error> {
+    let annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert_eq!(
+        annotation.links.incontext.as_deref(),
+        Some("https://hyp.is/Bvx0KJt2Ee-RVwMi3W2e8Q/www.example.com/")
+    );
+    assert!(annotation.links.extra.is_empty());
+    Ok(())
+}
+
+#[test]
+fn search_query_limit_out_of_range() {
+    assert!(SearchQuery::builder().limit(200).build().is_ok());
+    let error = SearchQuery::builder().limit(250).build().unwrap_err();
+    assert!(matches!(error, HypothesisError::BuilderError(message) if message.contains("limit")));
+    let error = SearchQuery::builder().offset(9801).build().unwrap_err();
+    assert!(matches!(error, HypothesisError::BuilderError(message) if message.contains("offset")));
+}
+
+#[test]
+fn deserialize_fragment_selector() -> Result<(), serde_json::Error> {
+    let selector: Selector = serde_json::from_str(
+        r#"{"type": "FragmentSelector", "value": "page=10", "conformsTo": "http://tools.ietf.org/rfc/rfc3778"}"#,
+    )?;
+    let fragment = match &selector {
+        Selector::FragmentSelector(fragment) => fragment,
+        _ => panic!("expected a FragmentSelector, got {:?}", selector),
+    };
+    assert_eq!(fragment.value, "page=10");
+    assert_eq!(
+        fragment.conforms_to.as_deref(),
+        Some("http://tools.ietf.org/rfc/rfc3778")
+    );
+    assert!(fragment.extra.is_empty());
+    assert_eq!(
+        serde_json::from_str::<Selector>(&serde_json::to_string(&selector)?)?,
+        selector
+    );
+    let selector: Selector =
+        serde_json::from_str(r#"{"type": "FragmentSelector", "value": "t=10", "extra": 1}"#)?;
+    assert_eq!(
+        selector,
+        Selector::FragmentSelector(FragmentSelector {
+            value: "t=10".into(),
+            conforms_to: None,
+            extra: [("extra".to_string(), serde_json::json!(1))].into(),
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn deserialize_refined_css_selector() -> Result<(), serde_json::Error> {
+    let json = r#"{
+        "type": "CssSelector",
+        "value": "main > p:nth-child(2)",
+        "refinedBy": {
+            "type": "TextQuoteSelector",
+            "exact": "illustrative examples",
+            "prefix": "for use in ",
+            "suffix": " in documents"
+        }
+    }"#;
+    let selector: Selector = serde_json::from_str(json)?;
+    let expected = Selector::CssSelector(CssSelector {
+        value: "main > p:nth-child(2)".into(),
+        refined_by: Some(Box::new(Selector::TextQuoteSelector(TextQuoteSelector {
+            exact: "illustrative examples".into(),
+            prefix: "for use in ".into(),
+            suffix: " in documents".into(),
+        }))),
+    });
+    assert_eq!(selector, expected);
+    assert_eq!(
+        serde_json::from_str::<Selector>(&serde_json::to_string(&selector)?)?,
+        expected
+    );
+    Ok(())
+}
+
+#[test]
+fn sort_annotations_by_created() -> Result<(), serde_json::Error> {
+    let first: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    let mut second = first.clone();
+    second.created = first.created + chrono::Duration::seconds(1);
+    let mut same_time = first.clone();
+    same_time.id = "Cvx0KJt2Ee-RVwMi3W2e8Q".into();
+    let mut annotations = vec![second.clone(), same_time.clone(), first.clone()];
+    annotations.sort_by(Annotation::cmp_by_created);
+    assert_eq!(annotations, vec![first, same_time, second]);
+    Ok(())
+}
+
+#[test]
+fn media_time_selector_round_trip() -> Result<(), serde_json::Error> {
+    let selector: Selector =
+        serde_json::from_str(r#"{"type": "MediaTimeSelector", "start": 12.5, "end": 30}"#)?;
+    assert_eq!(selector, Selector::new_media_time(12.5, 30.0));
+    assert_eq!(
+        serde_json::to_value(&selector)?,
+        serde_json::json!({"type": "MediaTimeSelector", "start": 12.5, "end": 30.0})
+    );
+    Ok(())
+}
+
+#[test]
+fn epub_content_selector_round_trip() -> Result<(), serde_json::Error> {
+    let json = serde_json::json!({
+        "type": "EPUBContentSelector",
+        "cfi": "/6/4",
+        "url": "https://example.com/book/chapter01.xhtml",
+        "title": "Chapter 1"
+    });
+    let selector: Selector = serde_json::from_value(json.clone())?;
+    assert_eq!(
+        selector,
+        Selector::EPUBContentSelector(EPUBContentSelector {
+            cfi: Some("/6/4".into()),
+            url: Some("https://example.com/book/chapter01.xhtml".into()),
+            title: Some("Chapter 1".into()),
+        })
+    );
+    assert_eq!(serde_json::to_value(&selector)?, json);
+
+    let json = serde_json::json!({"type": "EPUBContentSelector", "title": "Chapter 1"});
+    let selector: Selector = serde_json::from_value(json.clone())?;
+    assert_eq!(serde_json::to_value(&selector)?, json);
+    Ok(())
+}
+
+#[test]
+fn page_selector_round_trip() -> Result<(), serde_json::Error> {
+    let json = serde_json::json!({"type": "PageSelector", "label": "iv", "index": 3});
+    let selector: Selector = serde_json::from_value(json.clone())?;
+    assert_eq!(
+        selector,
+        Selector::PageSelector(PageSelector {
+            label: Some("iv".into()),
+            index: Some(3),
+        })
+    );
+    assert_eq!(serde_json::to_value(&selector)?, json);
+    Ok(())
+}
+
+#[test]
+fn search_query_tags_any_is_not_sent() -> Result<(), Box<dyn std::error::Error>> {
+    let query = SearchQuery::builder()
+        .tags_all(vec!["a".into(), "b".into()])
+        .tags_any(vec!["c".into()])
+        .build()?;
+    assert_eq!(query.tags, vec!["a".to_string(), "b".to_string()]);
+    let json = serde_json::to_value(&query)?;
+    assert_eq!(json["tags"], serde_json::json!(["a", "b"]));
+    assert!(json.get("tags_any").is_none());
+    Ok(())
+}
+
+#[test]
+fn input_annotation_multiple_targets() -> Result<(), Box<dyn std::error::Error>> {
+    let annotation = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .target(
+            Target::builder()
+                .source("https://www.example.com")
+                .selector(vec![Selector::new_quote("exact", "prefix", "suffix")])
+                .build()?,
+        )
+        .target(
+            Target::builder()
+                .source("https://www.example.com")
+                .selector(vec![Selector::new_text_position(38, 100)])
+                .build()?,
+        )
+        .build()?;
+    assert_eq!(annotation.targets.len(), 2);
+    let json = serde_json::to_value(&annotation)?;
+    assert_eq!(json["target"].as_array().map(Vec::len), Some(2));
+    assert_eq!(serde_json::from_value::<InputAnnotation>(json)?, annotation);
+
+    let legacy: InputAnnotation = serde_json::from_str(
+        r#"{"uri": "https://www.example.com", "target": {"source": "https://www.example.com", "selector": []}}"#,
+    )?;
+    assert_eq!(legacy.targets.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn input_annotation_display_name() -> Result<(), Box<dyn std::error::Error>> {
+    let annotation = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .display_name("Example User")
+        .build()?;
+    let json = serde_json::to_value(&annotation)?;
+    assert_eq!(
+        json["user_info"],
+        serde_json::json!({"display_name": "Example User"})
+    );
+    let annotation = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .build()?;
+    assert!(serde_json::to_value(&annotation)?
+        .get("user_info")
+        .is_none());
+    Ok(())
+}
+
+#[test]
+fn annotation_share_url() -> Result<(), serde_json::Error> {
+    let mut annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert_eq!(Some(annotation.share_url()), annotation.links.incontext);
+    assert!(!annotation.is_reply());
+    assert_eq!(annotation.root_reference(), None);
+    annotation.references = vec!["root_id".into(), "parent_id".into()];
+    assert!(annotation.is_reply());
+    assert_eq!(annotation.root_reference(), Some("root_id"));
+    annotation.uri = "urn:x-pdf:1234".into();
+    assert_eq!(annotation.share_url(), "https://hyp.is/root_id");
+    Ok(())
+}
+
+#[test]
+fn data_position_selector_round_trip() -> Result<(), serde_json::Error> {
+    let selector: Selector = serde_json::from_str(
+        r#"{"type": "DataPositionSelector", "start": 4096, "end": 4104, "unknown": true}"#,
+    )?;
+    assert_eq!(
+        selector,
+        Selector::DataPositionSelector(DataPositionSelector {
+            start: 4096,
+            end: 4104
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(&selector)?,
+        serde_json::json!({"type": "DataPositionSelector", "start": 4096, "end": 4104})
+    );
+    Ok(())
+}
+
+#[test]
+fn svg_selector_round_trip() -> Result<(), serde_json::Error> {
+    let json = serde_json::json!({
+        "type": "SvgSelector",
+        "value": "<svg:svg><polygon points=\"0,0 10,0 10,10\"/></svg:svg>"
+    });
+    let selector: Selector = serde_json::from_value(json.clone())?;
+    assert_eq!(
+        selector,
+        Selector::SvgSelector(SvgSelector {
+            value: "<svg:svg><polygon points=\"0,0 10,0 10,10\"/></svg:svg>".into(),
+            extra: Default::default(),
+        })
+    );
+    assert_eq!(serde_json::to_value(&selector)?, json);
+    let selector: Selector =
+        serde_json::from_str(r#"{"type": "SvgSelector", "id": "http://example.org/shape1.svg"}"#)?;
+    match &selector {
+        Selector::SvgSelector(svg) => {
+            assert!(svg.value.is_empty());
+            assert_eq!(svg.extra["id"], "http://example.org/shape1.svg");
+        }
+        _ => panic!("expected an SvgSelector, got {:?}", selector),
+    }
+    Ok(())
+}
+
+#[test]
+fn display_annotation() -> Result<(), serde_json::Error> {
+    let mut annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert_eq!(
+        annotation.to_string(),
+        "[Bvx0KJt2Ee-RVwMi3W2e8Q] example_user: \"This domain is for use in illustrative …\" → a comment (tag1)"
+    );
+    annotation.target.clear();
+    annotation.tags.clear();
+    annotation.text = "x".repeat(100);
+    assert_eq!(
+        annotation.to_string(),
+        format!("[Bvx0KJt2Ee-RVwMi3W2e8Q] example_user: {}…", "x".repeat(79))
+    );
+    Ok(())
+}
+
+#[test]
+fn search_query_wildcard_uri_validation() {
+    for wildcard_uri in ["https://example.com/*/page_", "example.com/*"] {
+        assert!(SearchQuery::builder()
+            .wildcard_uri(wildcard_uri)
+            .build()
+            .is_ok());
+    }
+    for wildcard_uri in ["http*://example.com/", "https://*.example.com/a"] {
+        let error = SearchQuery::builder()
+            .wildcard_uri(wildcard_uri)
+            .build()
+            .unwrap_err();
+        assert!(
+            matches!(error, HypothesisError::BuilderError(message) if message.contains("wildcard_uri"))
+        );
+    }
+}
+
+#[test]
+fn public_group_and_group_validation() -> Result<(), HypothesisError> {
+    let annotation = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .public_group()
+        .build()?;
+    assert_eq!(annotation.group, hypothesis::groups::PUBLIC_GROUP_ID);
+    let query = SearchQuery::builder().public_group().build()?;
+    assert_eq!(query.group, vec!["__world__".to_string()]);
+
+    let error = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .group("https://hypothes.is/groups/abc/name")
+        .build()
+        .unwrap_err();
+    assert!(matches!(error, HypothesisError::BuilderError(message) if message.contains("group")));
+    assert!(SearchQuery::builder()
+        .group(vec!["my group".to_string()])
+        .build()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn annotation_moderation() -> Result<(), serde_json::Error> {
+    let annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert_eq!(annotation.moderation, None);
+
+    let mut json: serde_json::Value = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    json["moderation"] = serde_json::json!({"flagCount": 2});
+    let annotation: Annotation = serde_json::from_value(json)?;
+    assert_eq!(annotation.moderation, Some(Moderation { flag_count: 2 }));
+    Ok(())
+}
+
+#[test]
+fn document_builder_doi_and_title() -> Result<(), HypothesisError> {
+    let document = Document::builder()
+        .title("A paper")
+        .doi("10.1000/xyz123")
+        .pdf_url("https://example.com/paper.pdf")
+        .build()?;
+    assert_eq!(document.title, vec!["A paper".to_string()]);
+    let highwire = document.highwire.unwrap();
+    assert_eq!(highwire.doi, vec!["10.1000/xyz123".to_string()]);
+    assert_eq!(
+        highwire.pdf_url,
+        vec!["https://example.com/paper.pdf".to_string()]
+    );
+    assert_eq!(
+        document.dc.unwrap().identifier,
+        vec!["10.1000/xyz123".to_string()]
+    );
+    assert!(document.link.is_empty());
+    Ok(())
+}
+
+#[test]
+fn new_quote_normalizes_exact() {
+    match Selector::new_quote(" exact\n  text\n", "prefix", "suffix") {
+        Selector::TextQuoteSelector(selector) => {
+            assert_eq!(selector.exact, "exact text");
+            assert_eq!(selector.prefix, "prefix ");
+            assert_eq!(selector.suffix, "\nsuffix");
+        }
+        selector => panic!("expected a TextQuoteSelector, got {:?}", selector),
+    }
+}
+
+#[test]
+fn annotation_contains_quote() -> Result<(), serde_json::Error> {
+    let annotation: Annotation = serde_json::from_str(WEB_CLIENT_ANNOTATION)?;
+    assert!(annotation.contains_quote("illustrative  examples"));
+    assert!(!annotation.contains_quote("examples illustrative"));
+    Ok(())
+}
+
+#[test]
+fn deserialize_search_result() -> Result<(), serde_json::Error> {
+    let json = format!(r#"{{"rows": [{}], "total": 12}}"#, WEB_CLIENT_ANNOTATION);
+    let result: SearchResult = serde_json::from_str(&json)?;
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.total, 12);
+    Ok(())
+}
+
+#[test]
+fn input_annotation_permissions() -> Result<(), Box<dyn std::error::Error>> {
+    let user = hypothesis::UserAccountID::new("username", "hypothes.is");
+    let annotation = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .permissions(Permissions::group_readable(&user, "__world__"))
+        .build()?;
+    let json = serde_json::to_value(&annotation)?;
+    assert_eq!(json["permissions"]["read"][0], "group:__world__");
+    assert_eq!(json["permissions"]["delete"][0], user.0.as_str());
+
+    let annotation = InputAnnotation::builder()
+        .uri("https://www.example.com")
+        .build()?;
+    assert!(serde_json::to_value(&annotation)?
+        .get("permissions")
+        .is_none());
+    Ok(())
+}
+
+#[test]
+fn text_position_selector_without_positions() -> Result<(), serde_json::Error> {
+    let json = serde_json::json!({"type": "TextPositionSelector"});
+    let selector: Selector = serde_json::from_value(json.clone())?;
+    assert_eq!(
+        selector,
+        Selector::TextPositionSelector(TextPositionSelector {
+            start: None,
+            end: None
+        })
+    );
+    assert_eq!(serde_json::to_value(&selector)?, json);
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+
+use hypothesis::annotations::{Annotation, InputAnnotation, SearchQuery};
+use hypothesis::api::HypothesisApi;
+use hypothesis::errors::HypothesisError;
+use hypothesis::groups::{Expand, Group, GroupFilters};
+use hypothesis::profile::UserProfile;
+
+/// Keeps annotations in memory instead of calling the API
+#[derive(Default)]
+struct MockApi {
+    annotations: Mutex<Vec<Annotation>>,
+}
+
+impl HypothesisApi for MockApi {
+    async fn create_annotation(
+        &self,
+        annotation: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        let mut annotations = self.annotations.lock().unwrap();
+        let created: Annotation = serde_json::from_value(serde_json::json!({
+            "id": annotations.len().to_string(),
+            "created": "2024-10-26T10:32:15.361455+00:00",
+            "updated": "2024-10-26T10:32:15.361455+00:00",
+            "user": "acct:mock_user@hypothes.is",
+            "uri": annotation.uri,
+            "text": annotation.text,
+            "tags": annotation.tags.clone().unwrap_or_default(),
+            "group": "__world__",
+            "permissions": {"read": [], "admin": [], "update": [], "delete": []},
+            "target": [],
+            "links": {},
+            "hidden": false,
+            "flagged": false
+        }))?;
+        annotations.push(created.clone());
+        Ok(created)
+    }
+
+    async fn update_annotation(&self, _: &Annotation) -> Result<Annotation, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn patch_annotation(
+        &self,
+        _: &str,
+        _: &InputAnnotation,
+    ) -> Result<Annotation, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn fetch_annotation(&self, _: &str) -> Result<Annotation, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn delete_annotation(&self, _: &str) -> Result<bool, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn search_annotations(
+        &self,
+        query: &SearchQuery,
+    ) -> Result<Vec<Annotation>, HypothesisError> {
+        let annotations = self.annotations.lock().unwrap();
+        Ok(annotations
+            .iter()
+            .filter(|annotation| query.tags.iter().all(|tag| annotation.tags.contains(tag)))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_groups(&self, _: &GroupFilters) -> Result<Vec<Group>, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn fetch_group(&self, _: &str, _: Vec<Expand>) -> Result<Group, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn fetch_user_profile(&self) -> Result<UserProfile, HypothesisError> {
+        unimplemented!()
+    }
+
+    async fn fetch_user_groups(&self) -> Result<Vec<Group>, HypothesisError> {
+        unimplemented!()
+    }
+}
+
+/// Downstream code written against the trait
+async fn tag_count(api: &impl HypothesisApi, tag: &str) -> Result<usize, HypothesisError> {
+    let query = SearchQuery::builder().tags(vec![tag.to_string()]).build()?;
+    Ok(api.search_annotations(&query).await?.len())
+}
+
+#[tokio::test]
+async fn mock_api() -> Result<(), HypothesisError> {
+    let api = MockApi::default();
+    for tags in [vec!["a"], vec!["a", "b"], vec!["b"]] {
+        api.create_annotation(
+            &InputAnnotation::builder()
+                .uri("https://www.example.com")
+                .tags(tags.into_iter().map(String::from).collect())
+                .build()?,
+        )
+        .await?;
+    }
+    assert_eq!(tag_count(&api, "a").await?, 2);
+    assert_eq!(tag_count(&api, "c").await?, 0);
+    Ok(())
+}
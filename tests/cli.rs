@@ -2,9 +2,10 @@
 
 use assert_cmd::Command;
 use predicates::prelude::*;
-use std::{thread, time};
+use std::{fs, thread, time};
 
-use hypothesis::annotations::Annotation;
+use hypothesis::annotations::{Annotation, InputAnnotation};
+use hypothesis::groups::Group;
 
 fn create_annotation(
     text: &str,
@@ -30,10 +31,10 @@ fn create_annotation(
 
 #[test]
 fn add_and_delete_annotation() -> color_eyre::Result<()> {
-    dotenv::dotenv()?;
-    let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
-    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    let key = dotenv::var("HYPOTHESIS_KEY")?;
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
 
     // Create a new annotation
     let id = create_annotation("test annotation comment", &username, &key, &group_id);
@@ -64,12 +65,174 @@ fn add_and_delete_annotation() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn fetch_annotation_as_csv() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+    let id = create_annotation("comma, \"quoted\" text", &username, &key, &group_id)?;
+
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("fetch")
+        .arg(&id)
+        .arg("--format=csv")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::starts_with("id,user,uri,text,tags,created\n")
+                .and(predicate::str::contains(&id))
+                .and(predicate::str::contains(r#","comma, ""quoted"" text","#)),
+        );
+
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("delete")
+        .arg(&id)
+        .assert()
+        .success();
+    Ok(())
+}
+
+#[test]
+fn add_annotation_with_quote() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+
+    // Create a new annotation highlighting some text
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("create")
+        .arg("--text=test annotation with quote")
+        .arg(&format!("--group={}", group_id))
+        .arg("--exact=illustrative examples")
+        .arg("--prefix=for use in ")
+        .arg("www.example.com")
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    let id = stdout
+        .split("annotation ")
+        .last()
+        .unwrap()
+        .trim()
+        .to_string();
+
+    // Fetch created annotation
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("fetch")
+        .arg(&id)
+        .assert()
+        .success();
+    let annotation: Annotation = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(annotation.quote(), Some("illustrative examples"));
+
+    // Prefix without exact text is rejected
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("create")
+        .arg("--prefix=for use in ")
+        .arg("www.example.com")
+        .assert()
+        .failure();
+
+    // Delete annotation
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("delete")
+        .arg(&id)
+        .assert()
+        .success();
+    Ok(())
+}
+
+#[test]
+fn add_annotation_from_json() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+
+    // Write an annotation to a JSON file
+    let input = InputAnnotation::builder()
+        .uri("www.example.com")
+        .text("test annotation from json")
+        .tags(vec!["json_tag".into()])
+        .group(&group_id)
+        .build()?;
+    let file = std::env::temp_dir().join("hypothesis_input_annotation.json");
+    fs::write(&file, serde_json::to_string(&input)?)?;
+
+    // Create annotation from the file
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("create")
+        .arg("--from-json")
+        .arg(&file)
+        .assert()
+        .success();
+    fs::remove_file(&file)?;
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    let id = stdout
+        .split("annotation ")
+        .last()
+        .unwrap()
+        .trim()
+        .to_string();
+
+    // Fetch created annotation
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("fetch")
+        .arg(&id)
+        .assert()
+        .success();
+    let annotation: Annotation = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(&annotation.text, "test annotation from json");
+    assert_eq!(annotation.tags, vec!["json_tag".to_string()]);
+
+    // Delete annotation
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("delete")
+        .arg(&id)
+        .assert()
+        .success();
+    Ok(())
+}
+
 #[test]
 fn update_annotation() -> color_eyre::Result<()> {
-    dotenv::dotenv()?;
-    let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
-    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    let key = dotenv::var("HYPOTHESIS_KEY")?;
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
 
     // Create a new annotation
     let id = create_annotation("test annotation comment", &username, &key, &group_id);
@@ -111,10 +274,10 @@ fn update_annotation() -> color_eyre::Result<()> {
 
 #[test]
 fn search_annotations() -> color_eyre::Result<()> {
-    dotenv::dotenv()?;
-    let group_id = dotenv::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
-    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    let key = dotenv::var("HYPOTHESIS_KEY")?;
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
     let ids = (0..4)
         .map(|i| create_annotation(&format!("test text {}", i), &username, &key, &group_id))
         .collect::<Result<Vec<_>, _>>()?;
@@ -151,6 +314,171 @@ fn search_annotations() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn search_annotations_to_file() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+    let id = create_annotation("test annotation to file", &username, &key, &group_id)?;
+
+    let duration = time::Duration::from_millis(500);
+    thread::sleep(duration);
+    let file = std::env::temp_dir().join(format!("hypothesis_search_{}.json", id));
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("search")
+        .arg("--limit=200")
+        .arg(&format!("--group={}", group_id))
+        .arg("-o")
+        .arg(&file)
+        .assert()
+        .success();
+    let contents = fs::read_to_string(&file)?;
+    let annotations = serde_json::Deserializer::from_str(&contents)
+        .into_iter::<Annotation>()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(annotations.iter().any(|annotation| annotation.id == id));
+    fs::remove_file(&file)?;
+
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("delete")
+        .arg(&id)
+        .assert()
+        .success();
+    Ok(())
+}
+
+#[test]
+fn import_annotations() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+    let id = create_annotation("test annotation to import", &username, &key, &group_id)?;
+
+    let file = std::env::temp_dir().join(format!("hypothesis_import_{}.json", id));
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("fetch")
+        .arg(&id)
+        .arg("-o")
+        .arg(&file)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 of 1 annotations"));
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    let imported_id = stdout
+        .lines()
+        .next()
+        .and_then(|line| line.split(" as ").last())
+        .unwrap_or_default()
+        .to_string();
+    assert!(!imported_id.is_empty());
+    assert_ne!(imported_id, id);
+    fs::remove_file(&file)?;
+
+    for id in [id, imported_id] {
+        let mut cmd = Command::cargo_bin("hypothesis")?;
+        cmd.env("HYPOTHESIS_NAME", &username)
+            .env("HYPOTHESIS_KEY", &key)
+            .arg("annotations")
+            .arg("delete")
+            .arg(&id)
+            .assert()
+            .success();
+    }
+    Ok(())
+}
+
+#[test]
+fn search_annotations_return_all() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let group_id = dotenvy::var("TEST_GROUP_ID").unwrap_or_else(|_| "__world__".into());
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+    let tag = format!(
+        "return_all_{}",
+        time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)?
+            .as_millis()
+    );
+    let mut ids = Vec::new();
+    for i in 0..55 {
+        let mut cmd = Command::cargo_bin("hypothesis")?;
+        let output = cmd
+            .env("HYPOTHESIS_NAME", &username)
+            .env("HYPOTHESIS_KEY", &key)
+            .arg("annotations")
+            .arg("create")
+            .arg(&format!("--text=test text {}", i))
+            .arg(&format!("--group={}", group_id))
+            .arg(&format!("--tags={}", tag))
+            .arg("www.example.com")
+            .assert()
+            .success();
+        let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+        ids.push(
+            stdout
+                .split("annotation ")
+                .last()
+                .unwrap()
+                .trim()
+                .to_string(),
+        );
+    }
+
+    let duration = time::Duration::from_millis(500);
+    thread::sleep(duration);
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("search")
+        .arg("--return-all")
+        .arg(&format!("--tag={}", tag))
+        .arg(&format!("--group={}", group_id))
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    let annotations = serde_json::Deserializer::from_str(&stdout)
+        .into_iter::<Annotation>()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(annotations.len(), 55);
+    assert!(annotations
+        .iter()
+        .all(|annotation| ids.contains(&annotation.id)));
+    for id in ids {
+        let mut cmd = Command::cargo_bin("hypothesis")?;
+        cmd.env("HYPOTHESIS_NAME", &username)
+            .env("HYPOTHESIS_KEY", &key)
+            .arg("annotations")
+            .arg("delete")
+            .arg(id)
+            .assert()
+            .success();
+    }
+    Ok(())
+}
+
 fn create_group(
     name: &str,
     description: &str,
@@ -174,9 +502,9 @@ fn create_group(
 
 #[test]
 fn create_and_leave_group() -> color_eyre::Result<()> {
-    dotenv::dotenv()?;
-    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    let key = dotenv::var("HYPOTHESIS_KEY")?;
+    dotenvy::dotenv()?;
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
 
     // Create a new group
     let group_id = create_group("test_name", "test description with spaces", &username, &key);
@@ -208,10 +536,44 @@ fn create_and_leave_group() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn create_and_delete_group() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+
+    // Create a new group
+    let group_id = create_group("test_name", "test description with spaces", &username, &key)?;
+
+    // Delete group
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("groups")
+        .arg("delete")
+        .arg(&group_id)
+        .assert()
+        .stdout(predicate::str::starts_with(format!(
+            "Deleted group {}",
+            &group_id
+        )));
+
+    // Fetching the deleted group fails
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("groups")
+        .arg("fetch")
+        .arg(&group_id)
+        .assert()
+        .failure();
+    Ok(())
+}
+
 #[test]
 fn update_group() -> color_eyre::Result<()> {
-    let username = dotenv::var("HYPOTHESIS_NAME")?;
-    let key = dotenv::var("HYPOTHESIS_KEY")?;
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
 
     // Create a new group
     let group_id = create_group("test_name", "test description with spaces", &username, &key);
@@ -257,3 +619,58 @@ fn update_group() -> color_eyre::Result<()> {
         )));
     Ok(())
 }
+
+#[test]
+fn list_groups_pretty() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    let output = cmd
+        .env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("profile")
+        .arg("groups")
+        .arg("--format=pretty")
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    let groups: Vec<Group> = serde_json::from_str(&stdout)?;
+    assert!(!groups.is_empty());
+    Ok(())
+}
+
+#[test]
+fn fetch_missing_annotation() -> color_eyre::Result<()> {
+    dotenvy::dotenv()?;
+    let username = dotenvy::var("HYPOTHESIS_NAME")?;
+    let key = dotenvy::var("HYPOTHESIS_KEY")?;
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", &username)
+        .env("HYPOTHESIS_KEY", &key)
+        .arg("annotations")
+        .arg("fetch")
+        .arg("badid")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Annotation badid not found"));
+    Ok(())
+}
+
+#[test]
+fn import_json_array() -> color_eyre::Result<()> {
+    // an empty import doesn't send any requests
+    let file = std::env::temp_dir().join("hypothesis_import_empty_array.json");
+    fs::write(&file, "[\n]")?;
+    let mut cmd = Command::cargo_bin("hypothesis")?;
+    cmd.env("HYPOTHESIS_NAME", "my_username")
+        .env("HYPOTHESIS_KEY", "6879-abcdefghijklmnop")
+        .arg("annotations")
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 0 of 0 annotations"));
+    fs::remove_file(&file)?;
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use futures::StreamExt;
+use hypothesis::annotations::SearchQuery;
+use hypothesis::errors::HypothesisError;
+use hypothesis::{Hypothesis, RateLimit};
+use reqwest::header::{HeaderMap, HeaderValue};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// Reads a whole HTTP request (headers and `Content-Length` body) so closing the connection
+/// after responding doesn't reset it
+fn read_request(stream: &mut TcpStream) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let read = stream.read(&mut buffer).unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let content_length = text[..end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if read == 0 || request.len() >= end + 4 + content_length {
+                return;
+            }
+        } else if read == 0 {
+            return;
+        }
+    }
+}
+
+/// Answers requests on a local port with a 200 and each of `bodies` in turn,
+/// returns the URL to use as `base_url`
+fn serve(bodies: Vec<String>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for body in bodies {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_request(&mut stream);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    url
+}
+
+fn local_client(bodies: Vec<String>) -> Result<Hypothesis, HypothesisError> {
+    Hypothesis::builder()
+        .username("my_username")
+        .developer_key("6879-abcdefghijklmnop")
+        .base_url(serve(bodies))
+        .max_retries(0)
+        // one request at a time, so responses arrive in the order of `bodies`
+        .max_concurrency(1)
+        .build()
+}
+
+/// Minimal annotation as returned by the API, in the group `{group}`
+const ANNOTATION: &str = r#"{
+    "id": "{id}",
+    "created": "2024-10-26T10:32:15.361455+00:00",
+    "updated": "2024-10-26T10:32:15.361455+00:00",
+    "user": "acct:my_username@hypothes.is",
+    "uri": "https://www.example.com/",
+    "text": "a comment",
+    "tags": [],
+    "group": "{group}",
+    "permissions": {"read": [], "admin": [], "update": [], "delete": []},
+    "target": [{"source": "https://www.example.com/"}],
+    "links": {},
+    "hidden": false,
+    "flagged": false
+}"#;
+
+fn annotation_json(id: &str, group: &str) -> String {
+    ANNOTATION.replace("{id}", id).replace("{group}", group)
+}
+
+#[test]
+fn key_with_trailing_newline() -> Result<(), HypothesisError> {
+    let api = Hypothesis::new("my_username\n", "6879-abcdefghijklmnop\n")?;
+    assert_eq!(api.username, "my_username");
+    Ok(())
+}
+
+#[test]
+fn malformed_key() {
+    for key in ["", "6879-abc\ndefghijklmnop", "6879-abcdéfghijklmnop"] {
+        assert!(matches!(
+            Hypothesis::new("my_username", key),
+            Err(HypothesisError::InvalidKey)
+        ));
+    }
+}
+
+#[test]
+fn oauth_token() -> Result<(), HypothesisError> {
+    let mut api = Hypothesis::with_oauth_token("my_username", "5768-abcdefghijklmnop")?;
+    api.set_token("5768-qrstuvwxyz\n")?;
+    assert!(matches!(
+        api.set_token("expired token"),
+        Err(HypothesisError::InvalidKey)
+    ));
+    assert!(matches!(
+        Hypothesis::builder().username("my_username").build(),
+        Err(HypothesisError::BuilderError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn api_error_shows_response_body() {
+    let error = HypothesisError::APIError {
+        source: Default::default(),
+        serde_error: None,
+        raw_text: "<html>502 Bad Gateway</html>\n".into(),
+        status: Some(reqwest::StatusCode::BAD_GATEWAY),
+    };
+    assert_eq!(
+        error.to_string(),
+        "Make sure input fields are valid (502 Bad Gateway): <html>502 Bad Gateway</html>"
+    );
+    let error = HypothesisError::APIError {
+        source: Default::default(),
+        serde_error: None,
+        raw_text: "x".repeat(1000),
+        status: None,
+    };
+    assert!(error
+        .to_string()
+        .ends_with(&format!(": {}...", "x".repeat(500))));
+}
+
+#[test]
+fn api_error_validation_details() -> Result<(), serde_json::Error> {
+    let error: hypothesis::errors::APIError = serde_json::from_str(
+        r#"{"status": "failure", "reason": "uri: Required", "errors": {"uri": "Required"}}"#,
+    )?;
+    assert_eq!(error.reason, "uri: Required");
+    assert_eq!(error.details, Some(serde_json::json!({"uri": "Required"})));
+    assert!(error
+        .to_string()
+        .ends_with(r#"Details: {"uri":"Required"}"#));
+    let error: hypothesis::errors::APIError =
+        serde_json::from_str(r#"{"status": "failure", "reason": "Not found"}"#)?;
+    assert!(error.details.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn unbounded_search_needs_opt_in() -> Result<(), HypothesisError> {
+    let api = Hypothesis::new("my_username", "6879-abcdefghijklmnop")?;
+    let mut query = SearchQuery::builder().limit(200).build()?;
+    assert!(query.is_unbounded());
+    assert!(matches!(
+        api.search_annotations_return_all(&mut query).await,
+        Err(HypothesisError::UnboundedSearch)
+    ));
+    assert!(!SearchQuery::builder().tag("tag").build()?.is_unbounded());
+    assert!(SearchQuery::builder()
+        .any("  ")
+        .tag(" ")
+        .tags(vec!["".into(), " ".into()])
+        .build()?
+        .is_unbounded());
+    assert!(
+        SearchQuery::builder()
+            .allow_unbounded(true)
+            .build()?
+            .allow_unbounded
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn unbounded_stream_needs_opt_in() -> Result<(), HypothesisError> {
+    let api = Hypothesis::new("my_username", "6879-abcdefghijklmnop")?;
+    let query = SearchQuery::builder().limit(200).build()?;
+    let stream = api.search_annotations_stream(query);
+    futures::pin_mut!(stream);
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(HypothesisError::UnboundedSearch))
+    ));
+    assert!(stream.next().await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn sync_since_needs_group() -> Result<(), HypothesisError> {
+    let api = Hypothesis::new("my_username", "6879-abcdefghijklmnop")?;
+    assert!(matches!(
+        api.sync_since(chrono::DateTime::default(), " ").await,
+        Err(HypothesisError::UnboundedSearch)
+    ));
+    // fails before fetching the annotation, there's no server to answer
+    let api = local_client(Vec::new())?;
+    assert!(matches!(
+        api.annotations_after("annotation", "").await,
+        Err(HypothesisError::UnboundedSearch)
+    ));
+    Ok(())
+}
+
+#[test]
+fn rate_limit_headers() -> Result<(), HypothesisError> {
+    let api = Hypothesis::new("my_username", "6879-abcdefghijklmnop")?;
+    assert_eq!(api.last_rate_limit(), None);
+
+    let mut headers = HeaderMap::new();
+    assert_eq!(RateLimit::from_headers(&headers), None);
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("42"));
+    headers.insert("X-RateLimit-Reset", HeaderValue::from_static("30"));
+    assert_eq!(
+        RateLimit::from_headers(&headers),
+        Some(RateLimit {
+            limit: None,
+            remaining: Some(42),
+            reset: Some(30),
+        })
+    );
+    Ok(())
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_error_from_request() {
+    use hypothesis::errors::{APIError, CLIError};
+    let error = HypothesisError::APIError {
+        source: Default::default(),
+        serde_error: None,
+        raw_text: String::new(),
+        status: Some(reqwest::StatusCode::NOT_FOUND),
+    };
+    assert_eq!(
+        CLIError::from_request(&error, "Annotation", "badid").to_string(),
+        "Annotation badid not found"
+    );
+    let error = HypothesisError::APIError {
+        source: APIError {
+            reason: "'text' is too long".into(),
+            ..Default::default()
+        },
+        serde_error: None,
+        raw_text: String::new(),
+        status: Some(reqwest::StatusCode::BAD_REQUEST),
+    };
+    assert_eq!(
+        CLIError::from_request(&error, "Annotation", "id").to_string(),
+        "Request failed: 'text' is too long"
+    );
+}
+
+#[test]
+fn api_error_needs_status_and_reason() {
+    for body in ["{}", r#"{"id": "group", "name": "Group"}"#] {
+        assert!(serde_json::from_str::<hypothesis::errors::APIError>(body).is_err());
+    }
+}
+
+#[tokio::test]
+async fn json_success_bodies_are_ok() -> Result<(), HypothesisError> {
+    local_client(vec!["{}".into()])?
+        .leave_group("group")
+        .await?;
+    local_client(vec!["{}".into()])?
+        .hide_annotation("annotation")
+        .await?;
+    let member = hypothesis::UserAccountID::new("someone", "hypothes.is");
+    local_client(vec![
+        r#"{"authority": "hypothes.is", "userid": "acct:someone@hypothes.is", "username": "someone", "display_name": null}"#
+            .into(),
+    ])?
+    .add_group_member("group", &member)
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn move_annotation_keeps_copy_if_original_not_deleted() -> Result<(), HypothesisError> {
+    let api = local_client(vec![
+        annotation_json("original", "__world__"),
+        annotation_json("copy", "group"),
+        r#"{"id": "original", "deleted": false}"#.into(),
+    ])?;
+    match api.move_annotation("original", "group").await {
+        Err(HypothesisError::MoveIncomplete { moved, source }) => {
+            assert_eq!(moved.id, "copy");
+            assert!(source.is_none());
+        }
+        other => panic!("expected MoveIncomplete, got {:?}", other.map(|a| a.id)),
+    }
+
+    let api = local_client(vec![
+        annotation_json("original", "__world__"),
+        annotation_json("copy", "group"),
+        r#"{"id": "original", "deleted": true}"#.into(),
+    ])?;
+    assert_eq!(api.move_annotation("original", "group").await?.id, "copy");
+    Ok(())
+}
+
+#[tokio::test]
+async fn move_annotations_dry_run_only_fetches() -> Result<(), HypothesisError> {
+    // the server stops after these two responses, so any other request would fail
+    let api = local_client(vec![
+        annotation_json("to_move", "__world__"),
+        annotation_json("already_moved", "group"),
+    ])?;
+    let to_move = api
+        .move_annotations_dry_run(&["to_move".into(), "already_moved".into()], "group")
+        .await?;
+    assert_eq!(
+        to_move.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(),
+        vec!["to_move"]
+    );
+    Ok(())
+}
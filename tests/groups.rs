@@ -0,0 +1,85 @@
+use hypothesis::groups::{Group, Member, Org, Organization, Role};
+
+/// Group as returned by the API with `expand=organization`
+const EXPANDED_GROUP: &str = r#"{
+    "id": "__world__",
+    "groupid": null,
+    "name": "Public",
+    "links": {"html": "https://hypothes.is/groups/__world__/public"},
+    "organization": {
+        "id": "__default__",
+        "default": true,
+        "logo": "https://hypothes.is/organizations/__default__/logo",
+        "name": "Hypothesis"
+    },
+    "scoped": false,
+    "type": "open"
+}"#;
+
+#[test]
+fn deserialize_expanded_organization() -> Result<(), serde_json::Error> {
+    let group: Group = serde_json::from_str(EXPANDED_GROUP)?;
+    assert_eq!(
+        group.organization,
+        Organization::Organization(Some(Org {
+            id: "__default__".into(),
+            default: true,
+            logo: Some("https://hypothes.is/organizations/__default__/logo".into()),
+            name: "Hypothesis".into(),
+        }))
+    );
+    assert_eq!(group.organization_name(), Some("Hypothesis"));
+    Ok(())
+}
+
+#[test]
+fn organization_name_unexpanded() -> Result<(), serde_json::Error> {
+    let mut json: serde_json::Value = serde_json::from_str(EXPANDED_GROUP)?;
+    json["organization"] = "__default__".into();
+    let group: Group = serde_json::from_value(json)?;
+    assert_eq!(
+        group.organization,
+        Organization::String("__default__".into())
+    );
+    assert_eq!(group.organization_name(), None);
+    Ok(())
+}
+
+#[test]
+fn can_annotate_by_type() -> Result<(), serde_json::Error> {
+    let mut json: serde_json::Value = serde_json::from_str(EXPANDED_GROUP)?;
+    assert!(serde_json::from_value::<Group>(json.clone())?.can_annotate());
+    json["type"] = "private".into();
+    assert!(serde_json::from_value::<Group>(json.clone())?.can_annotate());
+    json["type"] = "restricted".into();
+    assert!(!serde_json::from_value::<Group>(json)?.can_annotate());
+    Ok(())
+}
+
+#[test]
+fn member_roles() -> Result<(), serde_json::Error> {
+    let member: Member = serde_json::from_str(
+        r#"{
+            "authority": "hypothes.is",
+            "username": "someone",
+            "userid": "acct:someone@hypothes.is",
+            "display_name": null,
+            "roles": ["moderator"]
+        }"#,
+    )?;
+    assert_eq!(member.roles, vec![Role::Moderator]);
+    assert!(member.can_moderate());
+
+    let member: Member = serde_json::from_str(
+        r#"{"authority": "hypothes.is", "username": "someone", "userid": "acct:someone@hypothes.is"}"#,
+    )?;
+    assert!(member.roles.is_empty());
+    assert!(!member.can_moderate());
+
+    let member: Member = serde_json::from_str(
+        r#"{"authority": "hypothes.is", "username": "someone", "userid": "acct:someone@hypothes.is", "roles": ["member", "curator"]}"#,
+    )?;
+    assert_eq!(member.roles, vec![Role::Member, Role::Unknown]);
+    assert!(!member.can_moderate());
+    Ok(())
+}